@@ -0,0 +1,448 @@
+//! Signed attestations of the attack scenarios,
+//! used to generate indexer CSV fixtures and to test the pipeline against known attacks.
+
+use secp256k1::rand::thread_rng;
+use secp256k1::{generate_keypair, Message, Secp256k1, SecretKey};
+use serde_json::to_string;
+use sha3::{Digest, Keccak256};
+
+use proto_buf::indexer::IndexerEvent;
+
+use crate::did::{Did, Schema};
+use crate::schemas::status::{CredentialSubject, CurrentStatus, StatusSchema};
+use crate::schemas::trust::{
+	CredentialSubject as CredentialSubjectTrust, DomainTrust, TrustSchema,
+};
+use crate::schemas::{Domain, Proof};
+use crate::utils::address_from_ecdsa_key;
+
+pub const X_SK: &str = "7f6f2ccdb23f2abb7b69278e947c01c6160a31cf02c19d06d0f6e5ab1d768b95";
+pub const X: &str = "did:pkh:eth:0xa9572220348b1080264e81c0779f77c144790cd6";
+
+pub const Y_SK: &str = "117be1de549d1d4322c4711f11efa0c5137903124f85fc37c761ffc91ace30cb";
+pub const Y: &str = "did:pkh:eth:0xba9090181312bd0e40254a3dc29841980dd392d2";
+
+pub const Z_SK: &str = "ac7f0d9eaea4d4bf5438b887e34d0cf87e7f98d97da70eff001850487b2cae23";
+pub const Z: &str = "did:pkh:eth:0x9a2954b87d8745df0b1010291c51d68ae9269d43";
+
+pub const P_SK: &str = "bbb7d40b7bb8e41c550696fdef78fff6f013bb34627ba50ca2d63b6e84cffa6c";
+pub const P: &str = "did:pkh:eth:0x651a3c584f4c71b54c50ea73f41b936845ab4fdf";
+
+pub const Q_SK: &str = "9a32e1a6638ce87528a3f0303c7a9cecba4ed5fef0551f3afd1c7865bc66308f";
+pub const Q: &str = "did:pkh:eth:0x138aaabbc2ad61f8ea7f2d4155cc7323f26f8775";
+
+pub const S1: &str = "snap://0x90f8bf6a479f320ead074411a4b0e7944ea8c9c2";
+pub const S2: &str = "snap://0x90f8bf6a479f320ead074411a4b0e7944ea8c9c1";
+
+/// Target of the 100 sybils, distrusted by all of them.
+pub const SYBIL_TARGET: &str = "did:pkh:eth:0x90f8bf6a479f320ead074411a4b0e7944ea8c9c5";
+
+const START_TIMESTAMP: u64 = 2397848;
+const STATUS_SCHEMA_ID: u32 = 1;
+const TRUST_SCHEMA_ID: u32 = 2;
+
+impl StatusSchema {
+	pub fn generate(id: String, current_status: CurrentStatus) -> Self {
+		let did = Did::parse_snap(id.clone()).unwrap();
+		let mut keccak = Keccak256::default();
+		keccak.update([did.schema.into()]);
+		keccak.update(&did.key);
+		keccak.update([current_status.clone().into()]);
+		let digest = keccak.finalize();
+
+		let message = Message::from_digest_slice(digest.as_ref()).unwrap();
+
+		let rng = &mut thread_rng();
+		let (sk, pk) = generate_keypair(rng);
+		let secp = Secp256k1::new();
+		let res = secp.sign_ecdsa_recoverable(&message, &sk);
+		let (rec_id, sig_bytes) = res.serialize_compact();
+		let rec_id_i32 = rec_id.to_i32();
+
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&sig_bytes);
+		bytes.push(rec_id_i32.to_le_bytes()[0]);
+		let encoded_sig = hex::encode(bytes);
+
+		let kind = "StatusCredential".to_string();
+		let addr = address_from_ecdsa_key(&pk);
+		let issuer = format!("did:pkh:eth:0x{}", hex::encode(addr));
+		let cs = CredentialSubject::new(id, current_status);
+		let proof = Proof::new(encoded_sig);
+
+		StatusSchema::new(kind, issuer, cs, proof)
+	}
+
+	pub fn generate_from_sk(id: String, current_status: CurrentStatus, sk: SecretKey) -> Self {
+		let did = Did::parse_snap(id.clone()).unwrap();
+		let mut keccak = Keccak256::default();
+		keccak.update([did.schema.into()]);
+		keccak.update(&did.key);
+		keccak.update([current_status.clone().into()]);
+		let digest = keccak.finalize();
+
+		let message = Message::from_digest_slice(digest.as_ref()).unwrap();
+
+		let secp = Secp256k1::new();
+		let pk = sk.public_key(&secp);
+
+		let res = secp.sign_ecdsa_recoverable(&message, &sk);
+		let (rec_id, sig_bytes) = res.serialize_compact();
+		let rec_id_i32 = rec_id.to_i32();
+
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&sig_bytes);
+		bytes.push(rec_id_i32.to_le_bytes()[0]);
+		let encoded_sig = hex::encode(bytes);
+
+		let kind = "StatusCredential".to_string();
+		let addr = address_from_ecdsa_key(&pk);
+		let issuer = format!("did:pkh:eth:0x{}", hex::encode(addr));
+		let cs = CredentialSubject::new(id, current_status);
+		let proof = Proof::new(encoded_sig);
+
+		StatusSchema::new(kind, issuer, cs, proof)
+	}
+
+	pub fn generate_from_sk_string(
+		id: String, current_status: CurrentStatus, sk_string: String,
+	) -> Self {
+		let did = Did::parse_snap(id.clone()).unwrap();
+		let mut keccak = Keccak256::default();
+		keccak.update([did.schema.into()]);
+		keccak.update(&did.key);
+		keccak.update([current_status.clone().into()]);
+		let digest = keccak.finalize();
+
+		let message = Message::from_digest_slice(digest.as_ref()).unwrap();
+
+		let secp = Secp256k1::new();
+		let sk_bytes = hex::decode(sk_string).unwrap();
+		let sk = SecretKey::from_slice(&sk_bytes).unwrap();
+		let pk = sk.public_key(&secp);
+
+		let res = secp.sign_ecdsa_recoverable(&message, &sk);
+		let (rec_id, sig_bytes) = res.serialize_compact();
+		let rec_id_i32 = rec_id.to_i32();
+
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&sig_bytes);
+		bytes.push(rec_id_i32.to_le_bytes()[0]);
+		let encoded_sig = hex::encode(bytes);
+
+		let kind = "StatusCredential".to_string();
+		let addr = address_from_ecdsa_key(&pk);
+		let issuer = format!("did:pkh:eth:0x{}", hex::encode(addr));
+		let cs = CredentialSubject::new(id, current_status);
+		let proof = Proof::new(encoded_sig);
+
+		StatusSchema::new(kind, issuer, cs, proof)
+	}
+}
+
+impl TrustSchema {
+	pub fn generate_from_sk(did_string: String, trust_arc: DomainTrust, sk: SecretKey) -> Self {
+		let did = Did::parse_pkh_eth(did_string.clone()).unwrap();
+
+		let mut keccak = Keccak256::default();
+		keccak.update([did.schema.into()]);
+		keccak.update(&did.key);
+		keccak.update([trust_arc.scope.clone().into()]);
+		// keccak.update(&trust_arc.level.to_be_bytes());
+
+		let digest = keccak.finalize();
+
+		let message = Message::from_digest_slice(digest.as_ref()).unwrap();
+
+		let secp = Secp256k1::new();
+		let pk = sk.public_key(&secp);
+
+		let res = secp.sign_ecdsa_recoverable(&message, &sk);
+		let (rec_id, sig_bytes) = res.serialize_compact();
+		let rec_id = rec_id.to_i32().to_le_bytes()[0];
+
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&sig_bytes);
+		bytes.push(rec_id);
+		let sig_string = hex::encode(bytes);
+
+		let kind = "TrustCredential".to_string();
+		let addr = address_from_ecdsa_key(&pk);
+		let issuer = format!("did:pkh:eth:0x{}", hex::encode(addr));
+		let cs = CredentialSubjectTrust::new(did_string, vec![trust_arc]);
+		let proof = Proof::new(sig_string);
+
+		TrustSchema::new(kind, issuer, cs, proof)
+	}
+
+	pub fn generate_from_sk_string(
+		did_string: String, trust_arc: DomainTrust, sk_string: String,
+	) -> Self {
+		let did = Did::parse_pkh_eth(did_string.clone()).unwrap();
+
+		let mut keccak = Keccak256::default();
+		keccak.update([did.schema.into()]);
+		keccak.update(&did.key);
+		keccak.update([trust_arc.scope.clone().into()]);
+		// keccak.update(&trust_arc.level.to_be_bytes());
+
+		let digest = keccak.finalize();
+
+		let message = Message::from_digest_slice(digest.as_ref()).unwrap();
+
+		let secp = Secp256k1::new();
+		let sk_bytes = hex::decode(sk_string).unwrap();
+		let sk = SecretKey::from_slice(&sk_bytes).unwrap();
+		let pk = sk.public_key(&secp);
+
+		let res = secp.sign_ecdsa_recoverable(&message, &sk);
+		let (rec_id, sig_bytes) = res.serialize_compact();
+		let rec_id = rec_id.to_i32().to_le_bytes()[0];
+
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&sig_bytes);
+		bytes.push(rec_id);
+		let sig_string = hex::encode(bytes);
+
+		let kind = "TrustCredential".to_string();
+		let addr = address_from_ecdsa_key(&pk);
+		let issuer = format!("did:pkh:eth:0x{}", hex::encode(addr));
+		let cs = CredentialSubjectTrust::new(did_string, vec![trust_arc]);
+		let proof = Proof::new(sig_string);
+
+		TrustSchema::new(kind, issuer, cs, proof)
+	}
+}
+
+fn trust(issuer_sk: &str, subject: &str, domain: Domain, level: f32) -> TrustSchema {
+	TrustSchema::generate_from_sk_string(
+		subject.to_string(),
+		DomainTrust::new(domain, level, Vec::new()),
+		issuer_sk.to_string(),
+	)
+}
+
+fn status(issuer_sk: &str, snap: &str, current_status: CurrentStatus) -> StatusSchema {
+	StatusSchema::generate_from_sk_string(snap.to_string(), current_status, issuer_sk.to_string())
+}
+
+/// Indexer events of the attestations, trust credentials first,
+/// with consecutive ids and timestamps 1000 apart.
+fn events(trust_arcs: Vec<TrustSchema>, status_arcs: Vec<StatusSchema>) -> Vec<IndexerEvent> {
+	let trust_values = trust_arcs.iter().map(|x| (TRUST_SCHEMA_ID, to_string(x)));
+	let status_values = status_arcs.iter().map(|x| (STATUS_SCHEMA_ID, to_string(x)));
+	trust_values
+		.chain(status_values)
+		.zip(1..)
+		.map(|((schema_id, schema_value), id)| IndexerEvent {
+			id,
+			schema_id,
+			schema_value: schema_value.unwrap(),
+			timestamp: START_TIMESTAMP + (id - 1) * 1000,
+			..IndexerEvent::default()
+		})
+		.collect()
+}
+
+/// Indexer CSV of the events, as `id;timestamp;schema_id;schema_value` lines.
+pub fn to_csv(events: &[IndexerEvent]) -> String {
+	let mut csv = "id;timestamp;schema_id;schema_value\n".to_string();
+	for event in events {
+		let line = [
+			event.id.to_string(),
+			event.timestamp.to_string(),
+			event.schema_id.to_string(),
+			event.schema_value.clone(),
+		]
+		.join(";");
+		csv += &line;
+		csv += "\n";
+	}
+	csv
+}
+
+/// Trust and distrust across the Honesty and Software security domains,
+/// with both snaps endorsed and disputed.
+pub fn functional() -> Vec<IndexerEvent> {
+	// Trust
+	// p => x - Trust Credential - Honesty - trust
+	// x => z - Trust credential - Honesty - trust
+	// p => x - Trust Credential - Software security - trust
+	// q => y - Trust Credential - Software security - trust
+	// p => s1 - Status Credential - Endorse
+	// q => s2 - Status Credential - Endorse
+	// x => s1 - Status Credential - Endorse
+	let p_x1 = trust(P_SK, X, Domain::Honesty, 1.);
+	let x_z = trust(X_SK, Z, Domain::Honesty, 1.);
+	let p_x2 = trust(P_SK, X, Domain::SoftwareSecurity, 1.);
+	let q_y = trust(Q_SK, Y, Domain::SoftwareSecurity, 1.);
+
+	let q_s2 = status(Q_SK, S2, CurrentStatus::Endorsed);
+	let p_s1 = status(P_SK, S1, CurrentStatus::Endorsed);
+	let x_s1 = status(X_SK, S2, CurrentStatus::Endorsed);
+
+	// Distrust
+	// p => y - Trust Credential - Honest - distrust
+	// q => x - Trust Credential - Software security - distrust
+	// y => z - Trust Credential - Software security - distrust
+	// y => s2 - Status Credential - Dispute
+	// z => s1 - Status Credential - Dispute
+	// z => s2 - Status Credential - Dispute
+	let p_y = trust(P_SK, Y, Domain::Honesty, -1.);
+	let q_x = trust(Q_SK, X, Domain::SoftwareSecurity, -1.);
+	let y_z = trust(Y_SK, Z, Domain::SoftwareSecurity, -1.);
+
+	let y_s2 = status(Y_SK, S2, CurrentStatus::Disputed);
+	let z_s1 = status(Z_SK, S1, CurrentStatus::Disputed);
+	let z_s2 = status(Z_SK, S2, CurrentStatus::Disputed);
+
+	let trust_arcs = vec![p_x1, p_x2, q_y, x_z, p_y, q_x, y_z];
+	let status_arcs = vec![q_s2, p_s1, x_s1, y_s2, z_s1, z_s2];
+	events(trust_arcs, status_arcs)
+}
+
+/// The clique x, y, z endorses s1, disputed by p and q.
+/// q trusts y, so the clique gains some trust, while p distrusts all of its members.
+pub fn sybil_attack() -> Vec<IndexerEvent> {
+	// Trust - Direct
+	// x => y - Trust Credential - Software security - trust
+	// x => z - Trust Credential - Software security - trust
+	// y => x - Trust Credential - Software security - trust
+	// y => z - Trust Credential - Software security - trust
+	// z => x - Trust Credential - Software security - trust
+	// z => y - Trust Credential - Software security - trust
+	// q => y - Trust Credential - Software security - trust
+	let x_y = trust(X_SK, Y, Domain::SoftwareSecurity, 1.);
+	let x_z = trust(X_SK, Z, Domain::SoftwareSecurity, 1.);
+	let y_x = trust(Y_SK, X, Domain::SoftwareSecurity, 1.);
+	let y_z = trust(Y_SK, Z, Domain::SoftwareSecurity, 1.);
+	let z_x = trust(Z_SK, X, Domain::SoftwareSecurity, 1.);
+	let z_y = trust(Z_SK, Y, Domain::SoftwareSecurity, 1.);
+	let q_y = trust(Q_SK, Y, Domain::SoftwareSecurity, 1.);
+
+	// Trust - Snap
+	// x => s1 - Status Credential - Endorse
+	// y => s1 - Status Credential - Endorse
+	// z => s1 - Status Credential - Endorse
+	// p => s2 - Status Credential - Endorse
+	// q => s2 - Status Credential - Endorse
+	let x_s1 = status(X_SK, S1, CurrentStatus::Endorsed);
+	let y_s1 = status(Y_SK, S1, CurrentStatus::Endorsed);
+	let z_s1 = status(Z_SK, S1, CurrentStatus::Endorsed);
+	let p_s2 = status(P_SK, S2, CurrentStatus::Endorsed);
+	let q_s2 = status(Q_SK, S2, CurrentStatus::Endorsed);
+
+	// Distrust - Direct
+	// p => x - Trust Credential - Software security - distrust
+	// p => y - Trust Credential - Software security - distrust
+	// p => z - Trust Credential - Software security - distrust
+	// x => p - Trust Credential - Software security - distrust
+	// y => p - Trust Credential - Software security - distrust
+	// z => p - Trust Credential - Software security - distrust
+	let p_x = trust(P_SK, X, Domain::SoftwareSecurity, -1.);
+	let p_y = trust(P_SK, Y, Domain::SoftwareSecurity, -1.);
+	let p_z = trust(P_SK, Z, Domain::SoftwareSecurity, -1.);
+	let x_p = trust(X_SK, P, Domain::SoftwareSecurity, -1.);
+	let y_p = trust(Y_SK, P, Domain::SoftwareSecurity, -1.);
+	let z_p = trust(Z_SK, P, Domain::SoftwareSecurity, -1.);
+
+	// Distrust - Snap
+	// p => s1 - Status Credential - Dispute
+	// q => s1 - Status Credential - Dispute
+	let p_s1 = status(P_SK, S1, CurrentStatus::Disputed);
+	let q_s1 = status(Q_SK, S1, CurrentStatus::Disputed);
+
+	let trust_arcs = vec![x_y, x_z, y_x, y_z, z_x, z_y, q_y, p_x, p_y, p_z, x_p, y_p, z_p];
+	let status_arcs = vec![x_s1, y_s1, z_s1, p_s2, q_s2, p_s1, q_s1];
+	events(trust_arcs, status_arcs)
+}
+
+/// In the 1st round z builds up trust from p and q by endorsing s2 along with them.
+/// In the 2nd round z endorses s1 and turns on s2, disputing it.
+pub fn sleeping_agent_attack() -> (Vec<IndexerEvent>, Vec<IndexerEvent>) {
+	// Trust - Direct
+	// P => Q - Trust Credential - Software security - trust
+	// Q => P - Trust Credential - Software security - trust
+	// P => Z - Trust Credential - Software security - trust
+	// Q => Z - Trust Credential - Software security - trust
+	let p_q = trust(P_SK, Q, Domain::SoftwareSecurity, 1.);
+	let q_p = trust(Q_SK, P, Domain::SoftwareSecurity, 1.);
+	let p_z = trust(P_SK, Z, Domain::SoftwareSecurity, 1.);
+	let q_z = trust(Q_SK, Z, Domain::SoftwareSecurity, 1.);
+
+	// Trust - Snap
+	// P => S2 - Status Credential - Endorse
+	// Q => S2 - Status Credential - Endorse
+	// Z => S2 - Status Credential - Endorse
+	let p_s2 = status(P_SK, S2, CurrentStatus::Endorsed);
+	let q_s2 = status(Q_SK, S2, CurrentStatus::Endorsed);
+	let z_s2 = status(Z_SK, S2, CurrentStatus::Endorsed);
+
+	// Distrust - Direct
+	// P => X - Trust Credential - Software security - distrust
+	// P => Y - Trust Credential - Software security - distrust
+	// Q => X - Trust Credential - Software security - distrust
+	// Q => Y - Trust Credential - Software security - distrust
+	let p_x = trust(P_SK, X, Domain::SoftwareSecurity, -1.);
+	let p_y = trust(P_SK, Y, Domain::SoftwareSecurity, -1.);
+	let q_x = trust(Q_SK, X, Domain::SoftwareSecurity, -1.);
+	let q_y = trust(Q_SK, Y, Domain::SoftwareSecurity, -1.);
+
+	// Distrust - Snap
+	// Z => S2 - Status Credential - Dispute
+	let z_s2_override = status(Z_SK, S2, CurrentStatus::Disputed);
+
+	// Trust - Snap
+	// Z => S1 - Status Credential - Endorse
+	let z_s1 = status(Z_SK, S1, CurrentStatus::Endorsed);
+
+	// 1st round
+	let trust_arcs = vec![p_q, q_p, p_z, q_z, p_x, p_y, q_x, q_y];
+	let status_arcs_1st = vec![p_s2, q_s2, z_s2];
+	// 2nd round
+	let status_arcs_2nd = vec![z_s1, z_s2_override];
+
+	let num_2nd = status_arcs_2nd.len();
+	let mut first = events(trust_arcs, [status_arcs_1st, status_arcs_2nd].concat());
+	let second = first.split_off(first.len() - num_2nd);
+	(first, second)
+}
+
+/// 100 sybils, each distrusting the target and all the sybils created before it,
+/// endorse s1 and dispute s2.
+pub fn sybils_100() -> Vec<IndexerEvent> {
+	let num_trustees = 100;
+	let rng = &mut thread_rng();
+	let secp = Secp256k1::new();
+
+	let mut trustees = vec![SYBIL_TARGET.to_string()];
+	let mut trust_credentials = Vec::new();
+	let mut status_credentials = Vec::new();
+	for _ in 0..num_trustees {
+		let sk = SecretKey::new(rng);
+
+		for trustee in &trustees {
+			let trust_credential = TrustSchema::generate_from_sk(
+				trustee.clone(),
+				DomainTrust::new(Domain::SoftwareSecurity, -1., Vec::new()),
+				sk,
+			);
+			trust_credentials.push(trust_credential);
+		}
+
+		let pk = sk.public_key(&secp);
+		let addr = address_from_ecdsa_key(&pk);
+		let did = Did::new(Schema::PkhEth, addr);
+		let did_string: String = did.into();
+		trustees.push(did_string);
+
+		let endorsment_credential =
+			StatusSchema::generate_from_sk(S1.to_string(), CurrentStatus::Endorsed, sk);
+		let dispute_credential =
+			StatusSchema::generate_from_sk(S2.to_string(), CurrentStatus::Disputed, sk);
+		status_credentials.push(endorsment_credential);
+		status_credentials.push(dispute_credential);
+	}
+
+	events(trust_credentials, status_credentials)
+}
@@ -0,0 +1,7 @@
+pub mod did;
+pub mod error;
+pub mod fixtures;
+pub mod managers;
+pub mod schemas;
+pub mod term;
+pub mod utils;
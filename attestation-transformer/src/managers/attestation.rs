@@ -0,0 +1,200 @@
+use rocksdb::DB;
+
+use proto_buf::indexer::{EventKind, IndexerEvent};
+
+use crate::error::AttTrError;
+use crate::schemas::parse_event;
+use crate::term::{Term, TermKind};
+
+/// Registry of the terms each attestation was transformed into,
+/// so that they can be retracted once the attestation is updated or revoked.
+#[derive(Debug)]
+pub struct AttestationManager;
+
+impl AttestationManager {
+	pub fn read_attestation(db: &DB, id: &str) -> Result<Option<(u32, Vec<Term>)>, AttTrError> {
+		let cf = db.cf_handle("attestation").ok_or_else(|| AttTrError::NotFoundError)?;
+		let bytes_opt = db.get_cf(&cf, id).map_err(AttTrError::DbError)?;
+		let Some(mut bytes) = bytes_opt else {
+			return Ok(None);
+		};
+
+		let mut take = |n: usize| -> Result<Vec<u8>, AttTrError> {
+			if bytes.len() < n {
+				return Err(AttTrError::SerialisationError);
+			}
+			Ok(bytes.drain(..n).collect())
+		};
+		let to_u32 = |x: Vec<u8>| -> Result<u32, AttTrError> {
+			let x: [u8; 4] = x.try_into().map_err(|_| AttTrError::SerialisationError)?;
+			Ok(u32::from_be_bytes(x))
+		};
+
+		let schema_id = to_u32(take(4)?)?;
+		let num_terms = to_u32(take(4)?)?;
+		let mut terms = Vec::new();
+		for _ in 0..num_terms {
+			let len = to_u32(take(4)?)?;
+			let len = usize::try_from(len).map_err(|_| AttTrError::SerialisationError)?;
+			terms.push(Term::from_bytes(take(len)?)?);
+		}
+		Ok(Some((schema_id, terms)))
+	}
+
+	/// Record the schema of the attestation, along with its terms, as `schema_id`,
+	/// the number of terms, then the length-prefixed bytes of each term.
+	pub fn write_attestation(
+		db: &DB, id: &str, schema_id: u32, terms: &[Term],
+	) -> Result<(), AttTrError> {
+		let cf = db.cf_handle("attestation").ok_or_else(|| AttTrError::NotFoundError)?;
+		let num_terms = u32::try_from(terms.len()).map_err(|_| AttTrError::SerialisationError)?;
+
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&schema_id.to_be_bytes());
+		bytes.extend_from_slice(&num_terms.to_be_bytes());
+		for term in terms {
+			let term_bytes = term.clone().into_bytes()?;
+			let len =
+				u32::try_from(term_bytes.len()).map_err(|_| AttTrError::SerialisationError)?;
+			bytes.extend_from_slice(&len.to_be_bytes());
+			bytes.extend_from_slice(&term_bytes);
+		}
+		db.put_cf(&cf, id, bytes).map_err(AttTrError::DbError)
+	}
+
+	pub fn delete_attestation(db: &DB, id: &str) -> Result<(), AttTrError> {
+		let cf = db.cf_handle("attestation").ok_or_else(|| AttTrError::NotFoundError)?;
+		db.delete_cf(&cf, id).map_err(AttTrError::DbError)
+	}
+
+	/// Convert an indexed event into terms, keeping track of the attestations.
+	/// An update yields the tombstones of the previous terms, followed by the new ones.
+	/// A revocation yields the tombstones only.
+	/// Updates and revocations of unknown attestations are skipped, since their schema
+	/// is the one of the recorded attestation.
+	pub fn parse_event(db: &DB, event: IndexerEvent) -> Result<Vec<Term>, AttTrError> {
+		let id = event.attestation_id.clone();
+		match event.kind() {
+			EventKind::Recorded => {
+				let schema_id = event.schema_id;
+				let terms = parse_event(event)?;
+				if !id.is_empty() {
+					Self::write_attestation(db, &id, schema_id, &terms)?;
+				}
+				Ok(terms)
+			},
+			EventKind::Updated => {
+				let Some((schema_id, previous_terms)) = Self::read_attestation(db, &id)? else {
+					return Ok(Vec::new());
+				};
+				// Updates don't necessarily carry the schema
+				let event = IndexerEvent { schema_id, ..event };
+				let terms: Vec<Term> = parse_event(event)?
+					.into_iter()
+					.map(|x| x.with_kind(TermKind::Updated))
+					.collect();
+				Self::write_attestation(db, &id, schema_id, &terms)?;
+
+				let mut tombstones = tombstones(Some((schema_id, previous_terms)));
+				tombstones.extend(terms);
+				Ok(tombstones)
+			},
+			EventKind::Revoked => {
+				let previous = Self::read_attestation(db, &id)?;
+				Self::delete_attestation(db, &id)?;
+				Ok(tombstones(previous))
+			},
+		}
+	}
+}
+
+fn tombstones(previous: Option<(u32, Vec<Term>)>) -> Vec<Term> {
+	previous.map_or(Vec::new(), |(_, terms)| {
+		terms.into_iter().map(|x| x.with_kind(TermKind::Revoked)).collect()
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use rocksdb::{Options, DB};
+	use serde_json::to_string;
+
+	use crate::schemas::status::{CurrentStatus, StatusSchema};
+
+	use super::*;
+
+	#[test]
+	fn should_retract_terms_of_updated_and_revoked_attestations() {
+		let mut opts = Options::default();
+		opts.create_missing_column_families(true);
+		opts.create_if_missing(true);
+		let db = DB::open_cf(&opts, "att-pe-test-storage", vec!["attestation"]).unwrap();
+
+		let snap = "snap://0x90f8bf6a479f320ead074411a4b0e7944ea8c9c2".to_owned();
+		let status = |current_status| {
+			to_string(&StatusSchema::generate(snap.clone(), current_status)).unwrap()
+		};
+		let event = |kind: EventKind, schema_value: String, timestamp| IndexerEvent {
+			schema_id: 1,
+			schema_value,
+			timestamp,
+			kind: kind.into(),
+			attestation_id: "0x01".to_string(),
+			..IndexerEvent::default()
+		};
+
+		let recorded = AttestationManager::parse_event(
+			&db,
+			event(EventKind::Recorded, status(CurrentStatus::Endorsed), 1),
+		)
+		.unwrap();
+		assert_eq!(recorded.len(), 1);
+
+		// The schema of the update is the one of the recorded attestation
+		let mut updated_event = event(EventKind::Updated, status(CurrentStatus::Disputed), 2);
+		updated_event.schema_id = 0;
+		let updated = AttestationManager::parse_event(&db, updated_event).unwrap();
+		assert_eq!(updated.len(), 2);
+		assert_eq!(updated[0], recorded[0].clone().with_kind(TermKind::Revoked));
+		assert_eq!(updated[1].kind(), TermKind::Updated);
+
+		let revoked =
+			AttestationManager::parse_event(&db, event(EventKind::Revoked, String::new(), 3))
+				.unwrap();
+		assert_eq!(
+			revoked,
+			vec![updated[1].clone().with_kind(TermKind::Revoked)]
+		);
+		assert_eq!(
+			AttestationManager::read_attestation(&db, "0x01").unwrap(),
+			None
+		);
+
+		// Updates of unknown attestations are skipped, rather than parsed with their schema id
+		let updated =
+			AttestationManager::parse_event(&db, event(EventKind::Updated, "{}".to_string(), 4))
+				.unwrap();
+		assert!(updated.is_empty());
+		assert_eq!(
+			AttestationManager::read_attestation(&db, "0x01").unwrap(),
+			None
+		);
+	}
+
+	#[test]
+	fn should_reject_unknown_schemas() {
+		let mut opts = Options::default();
+		opts.create_missing_column_families(true);
+		opts.create_if_missing(true);
+		let db = DB::open_cf(&opts, "att-us-test-storage", vec!["attestation"]).unwrap();
+
+		let event = IndexerEvent {
+			schema_id: 3,
+			schema_value: "{}".to_string(),
+			attestation_id: "0x01".to_string(),
+			..IndexerEvent::default()
+		};
+		let res = AttestationManager::parse_event(&db, event);
+		assert!(matches!(res, Err(AttTrError::ParseError)));
+	}
+}
@@ -1,2 +1,3 @@
+pub mod attestation;
 pub mod checkpoint;
 pub mod term;
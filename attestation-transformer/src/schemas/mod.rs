@@ -1,9 +1,16 @@
 use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
 use secp256k1::{Message, PublicKey, Secp256k1};
 use serde_derive::{Deserialize, Serialize};
+use serde_json::from_str;
 use sha3::{Digest, Keccak256};
 
-use crate::{error::AttTrError, term::Term};
+use proto_buf::indexer::IndexerEvent;
+
+use crate::error::AttTrError;
+use crate::schemas::security::SecurityReportSchema;
+use crate::schemas::status::StatusSchema;
+use crate::schemas::trust::TrustSchema;
+use crate::term::{merge, Term};
 
 pub mod security;
 pub mod status;
@@ -71,17 +78,45 @@ pub enum SchemaType {
 	TrustCredential,
 }
 
-impl From<u32> for SchemaType {
-	fn from(value: u32) -> Self {
+impl TryFrom<u32> for SchemaType {
+	type Error = AttTrError;
+
+	fn try_from(value: u32) -> Result<Self, Self::Error> {
 		match value {
-			0 => Self::SecurityCredential,
-			1 => Self::StatusCredential,
-			2 => Self::TrustCredential,
-			_ => panic!("Invalid Schema type"),
+			0 => Ok(Self::SecurityCredential),
+			1 => Ok(Self::StatusCredential),
+			2 => Ok(Self::TrustCredential),
+			_ => Err(AttTrError::ParseError),
 		}
 	}
 }
 
+/// Validate the attestation of an indexed event and convert it into terms,
+/// at most one per local trust entry.
+pub fn parse_event(event: IndexerEvent) -> Result<Vec<Term>, AttTrError> {
+	let schema_id = event.schema_id;
+	let schema_type = SchemaType::try_from(schema_id)?;
+	let terms = match schema_type {
+		SchemaType::SecurityCredential => {
+			let parsed_att: SecurityReportSchema =
+				from_str(&event.schema_value).map_err(AttTrError::SerdeError)?;
+			parsed_att.into_term(event.timestamp)?
+		},
+		SchemaType::StatusCredential => {
+			let parsed_att: StatusSchema =
+				from_str(&event.schema_value).map_err(AttTrError::SerdeError)?;
+			parsed_att.into_term(event.timestamp)?
+		},
+		SchemaType::TrustCredential => {
+			let parsed_att: TrustSchema =
+				from_str(&event.schema_value).map_err(AttTrError::SerdeError)?;
+			parsed_att.into_term(event.timestamp)?
+		},
+	};
+
+	Ok(merge(terms))
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub enum Domain {
 	Honesty,
@@ -1,4 +1,4 @@
-use proto_buf::transformer::{Form, TermObject};
+use proto_buf::transformer::{Form, TermKind as TermKindPb, TermObject};
 
 use crate::error::AttTrError;
 
@@ -36,6 +36,47 @@ impl From<TermForm> for Form {
 	}
 }
 
+/// Whether a term adds a contribution, or retracts an earlier one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermKind {
+	Recorded,
+	Updated,
+	Revoked,
+}
+
+impl TryFrom<u8> for TermKind {
+	type Error = AttTrError;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Self::Recorded),
+			1 => Ok(Self::Updated),
+			2 => Ok(Self::Revoked),
+			_ => Err(AttTrError::SerialisationError),
+		}
+	}
+}
+
+impl From<TermKind> for u8 {
+	fn from(value: TermKind) -> Self {
+		match value {
+			TermKind::Recorded => 0,
+			TermKind::Updated => 1,
+			TermKind::Revoked => 2,
+		}
+	}
+}
+
+impl From<TermKind> for TermKindPb {
+	fn from(value: TermKind) -> Self {
+		match value {
+			TermKind::Recorded => Self::Recorded,
+			TermKind::Updated => Self::Updated,
+			TermKind::Revoked => Self::Revoked,
+		}
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Term {
 	from: String,
@@ -44,6 +85,7 @@ pub struct Term {
 	domain: u32,
 	form: TermForm,
 	timestamp: u64,
+	kind: TermKind,
 }
 
 impl Term {
@@ -57,9 +99,19 @@ impl Term {
 			domain,
 			form: if is_trust { TermForm::Trust } else { TermForm::Distrust },
 			timestamp,
+			kind: TermKind::Recorded,
 		}
 	}
 
+	pub fn kind(&self) -> TermKind {
+		self.kind
+	}
+
+	/// The same term, marked as the given kind.
+	pub fn with_kind(self, kind: TermKind) -> Term {
+		Term { kind, ..self }
+	}
+
 	pub fn into_bytes(self) -> Result<Vec<u8>, AttTrError> {
 		let mut bytes = Vec::new();
 
@@ -69,6 +121,7 @@ impl Term {
 		let domain_bytes = self.domain.to_be_bytes();
 		let form_byte: u8 = self.form.into();
 		let timestamp_bytes = self.timestamp.to_be_bytes();
+		let kind_byte: u8 = self.kind.into();
 
 		bytes.extend_from_slice(from_bytes);
 		bytes.extend_from_slice(to_bytes);
@@ -76,96 +129,88 @@ impl Term {
 		bytes.extend_from_slice(&domain_bytes);
 		bytes.push(form_byte);
 		bytes.extend_from_slice(&timestamp_bytes);
+		bytes.push(kind_byte);
 
 		Ok(bytes)
 	}
 
 	pub fn from_bytes(mut bytes: Vec<u8>) -> Result<Self, AttTrError> {
-		let term: Term = match bytes.len() {
-			// 54 + 49 + 4 + 4 + 1 + 8 = 120
-			// 54: did:pkh:eth:0x152d4dd8afe95f7c38103d7460befbed07dedd8f - from
-			// 49: snap://0x9dc6c239a0f3abad2094cd6891cdc56cdf8994f8 - to
-			// 4: f32 - weight
-			// 4: u32 - domain
-			// 1: u8 - form
-			// 8: u63 - timestamp
-			120 => {
-				let from_bytes: Vec<u8> = bytes.drain(..54).collect();
-				let to_bytes: Vec<u8> = bytes.drain(..49).collect();
-				let weight_bytes: [u8; 4] = bytes
-					.drain(..4)
-					.collect::<Vec<u8>>()
-					.try_into()
-					.map_err(|_| AttTrError::SerialisationError)?;
-				let domain_bytes: [u8; 4] = bytes
-					.drain(..4)
-					.collect::<Vec<u8>>()
-					.try_into()
-					.map_err(|_| AttTrError::SerialisationError)?;
-				let form_byte: u8 = bytes.remove(0);
-				let timestamp_bytes = bytes
-					.drain(..8)
-					.collect::<Vec<u8>>()
-					.try_into()
-					.map_err(|_| AttTrError::SerialisationError)?;
-
-				let from =
-					String::from_utf8(from_bytes).map_err(|_| AttTrError::SerialisationError)?;
-				let to = String::from_utf8(to_bytes).map_err(|_| AttTrError::SerialisationError)?;
-				let weight = f32::from_be_bytes(weight_bytes);
-				let domain = u32::from_be_bytes(domain_bytes);
-				let form = TermForm::from(form_byte);
-				let timestamp = u64::from_be_bytes(timestamp_bytes);
-
-				Term { from, to, weight, domain, form, timestamp }
-			},
-			// 54 + 54 + 4 + 4 + 1 + 8 = 125
-			// 54: did:pkh:eth:0x152d4dd8afe95f7c38103d7460befbed07dedd8f - from
-			// 54: did:pkh:eth:0x152d4dd8afe95f7c38103d7460befbed07dedd8f - to
-			// 4: f32 - weight
-			// 4: u32 - domain
-			// 1: u8 - form
-			// 8: u63 - timestamp
-			125 => {
-				let from_bytes: Vec<u8> = bytes.drain(..54).collect();
-				let to_bytes: Vec<u8> = bytes.drain(..54).collect();
-				let weight_bytes: [u8; 4] = bytes
-					.drain(..4)
-					.collect::<Vec<u8>>()
-					.try_into()
-					.map_err(|_| AttTrError::SerialisationError)?;
-				let domain_bytes: [u8; 4] = bytes
-					.drain(..4)
-					.collect::<Vec<u8>>()
-					.try_into()
-					.map_err(|_| AttTrError::SerialisationError)?;
-				let form_byte: u8 = bytes.remove(0);
-				let timestamp_bytes = bytes
-					.drain(..8)
-					.collect::<Vec<u8>>()
-					.try_into()
-					.map_err(|_| AttTrError::SerialisationError)?;
-
-				let from =
-					String::from_utf8(from_bytes).map_err(|_| AttTrError::SerialisationError)?;
-				let to = String::from_utf8(to_bytes).map_err(|_| AttTrError::SerialisationError)?;
-				let weight = f32::from_be_bytes(weight_bytes);
-				let domain = u32::from_be_bytes(domain_bytes);
-				let form = TermForm::from(form_byte);
-				let timestamp = u64::from_be_bytes(timestamp_bytes);
-
-				Term { from, to, weight, domain, form, timestamp }
-			},
+		// 54 + 49 + 4 + 4 + 1 + 8 + 1 = 121
+		// 54: did:pkh:eth:0x152d4dd8afe95f7c38103d7460befbed07dedd8f - from
+		// 49: snap://0x9dc6c239a0f3abad2094cd6891cdc56cdf8994f8 - to
+		// 4: f32 - weight
+		// 4: u32 - domain
+		// 1: u8 - form
+		// 8: u63 - timestamp
+		// 1: u8 - kind, missing from the terms written before revocations
+		//
+		// 54 + 54 + 4 + 4 + 1 + 8 + 1 = 126
+		// 54: did:pkh:eth:0x152d4dd8afe95f7c38103d7460befbed07dedd8f - to
+		let (to_len, has_kind) = match bytes.len() {
+			120 => (49, false),
+			121 => (49, true),
+			125 => (54, false),
+			126 => (54, true),
 			_ => return Err(AttTrError::SerialisationError),
 		};
 
-		Ok(term)
+		let from_bytes: Vec<u8> = bytes.drain(..54).collect();
+		let to_bytes: Vec<u8> = bytes.drain(..to_len).collect();
+		let weight_bytes: [u8; 4] = bytes
+			.drain(..4)
+			.collect::<Vec<u8>>()
+			.try_into()
+			.map_err(|_| AttTrError::SerialisationError)?;
+		let domain_bytes: [u8; 4] = bytes
+			.drain(..4)
+			.collect::<Vec<u8>>()
+			.try_into()
+			.map_err(|_| AttTrError::SerialisationError)?;
+		let form_byte: u8 = bytes.remove(0);
+		let timestamp_bytes = bytes
+			.drain(..8)
+			.collect::<Vec<u8>>()
+			.try_into()
+			.map_err(|_| AttTrError::SerialisationError)?;
+		let kind_byte = if has_kind { bytes.remove(0) } else { 0 };
+
+		let from = String::from_utf8(from_bytes).map_err(|_| AttTrError::SerialisationError)?;
+		let to = String::from_utf8(to_bytes).map_err(|_| AttTrError::SerialisationError)?;
+		let weight = f32::from_be_bytes(weight_bytes);
+		let domain = u32::from_be_bytes(domain_bytes);
+		let form = TermForm::from(form_byte);
+		let timestamp = u64::from_be_bytes(timestamp_bytes);
+		let kind = TermKind::try_from(kind_byte)?;
+
+		Ok(Term { from, to, weight, domain, form, timestamp, kind })
+	}
+}
+
+/// Sum up the weights of the terms with the same truster, trustee, domain and form,
+/// so that an attestation makes a single contribution to each of the local trust entries.
+pub fn merge(terms: Vec<Term>) -> Vec<Term> {
+	let mut merged: Vec<Term> = Vec::new();
+	for term in terms {
+		let same = merged.iter_mut().find(|x| {
+			x.from == term.from
+				&& x.to == term.to
+				&& x.domain == term.domain
+				&& x.form == term.form
+				&& x.timestamp == term.timestamp
+				&& x.kind == term.kind
+		});
+		match same {
+			Some(x) => x.weight += term.weight,
+			None => merged.push(term),
+		}
 	}
+	merged
 }
 
 impl From<Term> for TermObject {
 	fn from(value: Term) -> Self {
 		let form: Form = value.form.into();
+		let kind: TermKindPb = value.kind.into();
 		Self {
 			from: value.from,
 			to: value.to,
@@ -173,6 +218,7 @@ impl From<Term> for TermObject {
 			domain: value.domain,
 			form: form.into(),
 			timestamp: value.timestamp,
+			kind: kind.into(),
 		}
 	}
 }
@@ -190,11 +236,33 @@ mod test {
 			domain: 67834578,
 			form: TermForm::Trust,
 			timestamp: 0,
+			kind: TermKind::Revoked,
 		};
 
 		let bytes = term.clone().into_bytes().unwrap();
-		let rec_term = Term::from_bytes(bytes).unwrap();
+		let rec_term = Term::from_bytes(bytes.clone()).unwrap();
 
 		assert_eq!(term, rec_term);
+
+		// Terms stored without a kind are recorded ones
+		let rec_term = Term::from_bytes(bytes[..bytes.len() - 1].to_vec()).unwrap();
+		assert_eq!(rec_term, term.with_kind(TermKind::Recorded));
+	}
+
+	#[test]
+	fn should_merge_terms_of_same_entry() {
+		let term = |to: &str, weight: f32, is_trust: bool| {
+			Term::new("alice".to_string(), to.to_string(), weight, 2, is_trust, 0)
+		};
+		let terms = merge(vec![
+			term("bob", 10., true),
+			term("bob", 1., true),
+			term("bob", 5., false),
+			term("carol", 1., true),
+		]);
+		assert_eq!(
+			terms,
+			vec![term("bob", 11., true), term("bob", 5., false), term("carol", 1., true)]
+		);
 	}
 }
@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use tonic::transport::Channel;
+use tonic::Request;
+
+use proto_buf::combiner::linear_combiner_client::LinearCombinerClient;
+use proto_buf::combiner::{LtBatch, LtHistoryBatch, MappingQuery};
+use proto_buf::transformer::Form;
+
+use crate::error::CoreError;
+use crate::matrix::LocalTrust;
+use crate::sync::LtSource;
+
+const MAX_MAPPING_SIZE: u32 = 1_000_000;
+pub const NEW_DATA_BATCH_SIZE: u32 = 10_000;
+const SNAP_PREFIX: &str = "snap://";
+
+/// Reads local trust and DID mappings from the linear combiner.
+#[derive(Debug, Clone)]
+pub struct Combiner {
+	client: LinearCombinerClient<Channel>,
+}
+
+impl Combiner {
+	pub fn new(channel: Channel) -> Self {
+		Self { client: LinearCombinerClient::new(channel) }
+	}
+
+	/// Read the index -> DID mapping of all participants known to the combiner.
+	pub async fn get_did_mapping(&mut self) -> Result<BTreeMap<u32, String>, CoreError> {
+		let query = MappingQuery { start: 0, size: MAX_MAPPING_SIZE };
+		let mut stream = self
+			.client
+			.get_did_mapping(Request::new(query))
+			.await
+			.map_err(CoreError::GrpcError)?
+			.into_inner();
+
+		let mut mapping = BTreeMap::new();
+		while let Some(res) = stream.message().await.map_err(CoreError::GrpcError)? {
+			mapping.insert(res.id, parse_did(&res.did)?);
+		}
+		Ok(mapping)
+	}
+
+	/// Read the full local trust matrix of the given domain and form.
+	pub async fn get_local_trust(
+		&mut self, domain: u32, form: Form, size: u32,
+	) -> Result<LocalTrust, CoreError> {
+		let mut lt = LocalTrust::new(size as usize);
+		if size == 0 {
+			return Ok(lt);
+		}
+
+		let batch =
+			LtHistoryBatch { domain, form: form.into(), x0: 0, y0: 0, x1: size - 1, y1: size - 1 };
+		let mut stream = self
+			.client
+			.get_historic_data(Request::new(batch))
+			.await
+			.map_err(CoreError::GrpcError)?
+			.into_inner();
+
+		while let Some(res) = stream.message().await.map_err(CoreError::GrpcError)? {
+			lt.set(res.x, res.y, f64::from(res.value));
+		}
+		Ok(lt)
+	}
+
+	/// Read (and consume) a batch of the entries of the given domain and form
+	/// updated since the last read, at most `NEW_DATA_BATCH_SIZE` of them.
+	/// The values are the new ones, not the differences from the previous ones.
+	pub async fn get_new_batch(
+		&mut self, domain: u32, form: Form,
+	) -> Result<Vec<(u32, u32, f64)>, CoreError> {
+		let batch = LtBatch { domain, form: form.into(), size: NEW_DATA_BATCH_SIZE };
+		let mut stream = self
+			.client
+			.get_new_data(Request::new(batch))
+			.await
+			.map_err(CoreError::GrpcError)?
+			.into_inner();
+
+		let mut entries = Vec::new();
+		while let Some(res) = stream.message().await.map_err(CoreError::GrpcError)? {
+			entries.push((res.x, res.y, f64::from(res.value)));
+		}
+		Ok(entries)
+	}
+}
+
+#[tonic::async_trait]
+impl LtSource for Combiner {
+	async fn get_new_batch(
+		&mut self, domain: u32, form: Form,
+	) -> Result<Vec<(u32, u32, f64)>, CoreError> {
+		Combiner::get_new_batch(self, domain, form).await
+	}
+
+	async fn get_did_mapping(&mut self) -> Result<BTreeMap<u32, String>, CoreError> {
+		Combiner::get_did_mapping(self).await
+	}
+
+	async fn get_local_trust(
+		&mut self, domain: u32, form: Form, size: u32,
+	) -> Result<LocalTrust, CoreError> {
+		Combiner::get_local_trust(self, domain, form, size).await
+	}
+}
+
+/// The combiner stores DIDs as raw bytes and hands them out hex encoded.
+fn parse_did(did: &str) -> Result<String, CoreError> {
+	let bytes = hex::decode(did).map_err(|_| CoreError::ParseError)?;
+	String::from_utf8(bytes).map_err(|_| CoreError::ParseError)
+}
+
+/// Whether the participant is a peer (as opposed to a snap).
+pub fn is_peer(did: &str) -> bool {
+	!did.starts_with(SNAP_PREFIX)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_parse_hex_encoded_did() {
+		let did = "did:pkh:eth:0x90f8bf6a479f320ead074411a4b0e7944ea8c9c2";
+		let encoded = hex::encode(did);
+		assert_eq!(parse_did(&encoded).unwrap(), did);
+		assert!(parse_did("not hex").is_err());
+	}
+
+	#[test]
+	fn should_distinguish_peers_from_snaps() {
+		assert!(is_peer(
+			"did:pkh:eth:0x90f8bf6a479f320ead074411a4b0e7944ea8c9c2"
+		));
+		assert!(!is_peer(
+			"snap://0x90f8bf6a479f320ead074411a4b0e7944ea8c9c2"
+		));
+	}
+}
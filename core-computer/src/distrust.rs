@@ -0,0 +1,61 @@
+use crate::error::CoreError;
+use crate::matrix::LocalTrust;
+
+/// Trust standing of the peers of a domain, indexed the same way as the local trust matrix.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerScores {
+	/// `T+`: EigenTrust score over positive local trust only (Phase 1a).
+	pub positive: Vec<f64>,
+	/// `T`: distrust-adjusted score (Phase 1b), within [-1, 1].
+	pub adjusted: Vec<f64>,
+}
+
+/// Apply the one-shot distrust discount on top of the Phase 1a scores.
+/// Each peer with a positive standing splits its own score across the peers it distrusts,
+/// proportionally to its (row-normalized) distrust opinions,
+/// and those peers receive the corresponding deduction.
+/// Distrust opinions of peers without a positive standing don't matter.
+pub fn adjust(positive: &[f64], distrust: &LocalTrust) -> Result<PeerScores, CoreError> {
+	if distrust.size() != positive.len() {
+		return Err(CoreError::InvalidParamsError(format!(
+			"distrust size {} doesn't match score size {}",
+			distrust.size(),
+			positive.len()
+		)));
+	}
+
+	let mut adjusted = positive.to_vec();
+	for (x, y, value) in distrust.normalize().entries() {
+		let standing = positive[x as usize];
+		if standing > 0. {
+			adjusted[y as usize] -= value * standing;
+		}
+	}
+
+	Ok(PeerScores { positive: positive.to_vec(), adjusted })
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_split_deduction_across_distrusted_peers() {
+		let mut distrust = LocalTrust::new(4);
+		distrust.set(0, 1, 1.);
+		distrust.set(0, 2, 1.);
+		// Self-distrust doesn't count
+		distrust.set(0, 0, 1.);
+		// Zero standing, so the opinion doesn't matter
+		distrust.set(3, 1, 1.);
+
+		let scores = adjust(&[0.5, 0.375, 0.125, 0.], &distrust).unwrap();
+		assert_eq!(scores.positive, vec![0.5, 0.375, 0.125, 0.]);
+		assert_eq!(scores.adjusted, vec![0.5, 0.125, -0.125, 0.]);
+	}
+
+	#[test]
+	fn should_reject_size_mismatch() {
+		assert!(adjust(&[1.], &LocalTrust::new(2)).is_err());
+	}
+}
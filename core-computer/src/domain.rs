@@ -0,0 +1,43 @@
+use std::env;
+
+use crate::eigentrust::ComputeParams;
+use crate::error::CoreError;
+
+/// Domain ids, as assigned by the attestation transformer.
+pub const SOFTWARE_DEVELOPMENT: u32 = 1;
+pub const SOFTWARE_SECURITY: u32 = 2;
+
+/// Independent EigenTrust run over the local trust of a single domain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DomainConfig {
+	pub domain: u32,
+	/// Scope of the published scores.
+	pub scope: String,
+	pub params: ComputeParams,
+}
+
+impl DomainConfig {
+	pub fn new(domain: u32, scope: &str) -> Self {
+		Self { domain, scope: scope.to_string(), params: ComputeParams::default() }
+	}
+
+	/// The developer and the auditor (security) scores of the peers.
+	pub fn defaults() -> Vec<Self> {
+		vec![
+			Self::new(SOFTWARE_DEVELOPMENT, "SoftwareDevelopment"),
+			Self::new(SOFTWARE_SECURITY, "SoftwareSecurity"),
+		]
+	}
+
+	/// The default domains, with `CORE_COMPUTER_ALPHA_<domain>` overriding their alpha.
+	pub fn from_env() -> Result<Vec<Self>, CoreError> {
+		let mut domains = Self::defaults();
+		for config in &mut domains {
+			if let Ok(alpha) = env::var(format!("CORE_COMPUTER_ALPHA_{}", config.domain)) {
+				config.params.alpha = alpha.parse().map_err(|_| CoreError::ParseError)?;
+			}
+			config.params.validate()?;
+		}
+		Ok(domains)
+	}
+}
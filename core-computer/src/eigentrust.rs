@@ -0,0 +1,351 @@
+use std::time::{Duration, Instant};
+
+use crate::error::CoreError;
+use crate::matrix::{CscMatrix, LocalTrust};
+
+const DEFAULT_ALPHA: f64 = 0.5;
+const DEFAULT_EPSILON: f64 = 1e-6;
+// iterations performed at most when unlimited, as an input may never converge
+const MAX_ITERATIONS: u32 = 10_000;
+
+/// EigenTrust parameters, as described by `ComputeParams` in `eigentrust.proto`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputeParams {
+	/// Pre-trust strength.
+	pub alpha: f64,
+	/// Convergence exit criteria, compared against the L1 distance of consecutive iterations.
+	pub epsilon: f64,
+	/// Maximum number of iterations to perform, 0: unlimited, up to an internal cap.
+	pub max_iterations: u32,
+	/// Flat-tail length, 0: disabled.
+	/// When set, convergence is declared once the ranking of the top peers
+	/// has held steady for this many consecutive iterations, instead of using epsilon.
+	pub flat_tail: u32,
+	/// Number of top peers whose ranking is tracked for flat-tail, 0: all peers.
+	pub num_leaders: u32,
+}
+
+impl Default for ComputeParams {
+	fn default() -> Self {
+		Self {
+			alpha: DEFAULT_ALPHA,
+			epsilon: DEFAULT_EPSILON,
+			max_iterations: 0,
+			flat_tail: 0,
+			num_leaders: 0,
+		}
+	}
+}
+
+impl ComputeParams {
+	pub fn validate(&self) -> Result<(), CoreError> {
+		if !(0. ..=1.).contains(&self.alpha) {
+			return Err(CoreError::InvalidParamsError(format!(
+				"alpha must be within [0, 1], got {}",
+				self.alpha
+			)));
+		}
+		if self.epsilon.is_nan() || self.epsilon <= 0. {
+			return Err(CoreError::InvalidParamsError(format!(
+				"epsilon must be positive, got {}",
+				self.epsilon
+			)));
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputeResult {
+	/// Global trust, indexed the same way as the local trust matrix.
+	pub scores: Vec<f64>,
+	pub iterations: u32,
+	/// Number of consecutive iterations (up to the last one)
+	/// without a change in the ranking of the top peers, 0 if flat-tail is disabled.
+	pub flat_tail: u32,
+	/// Time spent iterating.
+	pub elapsed: Duration,
+}
+
+impl ComputeResult {
+	pub fn iterations_per_second(&self) -> f64 {
+		let secs = self.elapsed.as_secs_f64();
+		if secs > 0. {
+			f64::from(self.iterations) / secs
+		} else {
+			0.
+		}
+	}
+}
+
+/// Normalize the pre-trust vector so that it sums up to 1.
+/// An empty (all-zero) pre-trust is replaced by a uniform distribution.
+pub fn normalize_pre_trust(pre_trust: &[f64]) -> Vec<f64> {
+	let sum: f64 = pre_trust.iter().filter(|x| **x > 0.).sum();
+	if sum <= 0. {
+		let size = pre_trust.len() as f64;
+		return vec![1. / size; pre_trust.len()];
+	}
+	pre_trust.iter().map(|x| if *x > 0. { x / sum } else { 0. }).collect()
+}
+
+/// Run the EigenTrust power iteration:
+/// `t(k+1) = (1 - alpha) * C^T * t(k) + alpha * p`,
+/// where `C` is the row-normalized local trust and `p` the normalized pre-trust.
+/// The trust held by dangling peers (without any outgoing trust) is redistributed along `p`.
+/// Iteration starts from the initial vector if it has any trust in it, otherwise from `p`.
+pub fn eigentrust(
+	lt: &LocalTrust, pre_trust: &[f64], initial: Option<&[f64]>, params: &ComputeParams,
+) -> Result<ComputeResult, CoreError> {
+	eigentrust_csc(
+		&CscMatrix::from(&lt.normalize()),
+		pre_trust,
+		initial,
+		params,
+	)
+}
+
+/// `eigentrust` over a local trust already row-normalized and converted to CSC,
+/// so that it can be kept across computes.
+pub fn eigentrust_csc(
+	c: &CscMatrix, pre_trust: &[f64], initial: Option<&[f64]>, params: &ComputeParams,
+) -> Result<ComputeResult, CoreError> {
+	params.validate()?;
+	let vectors = [Some(pre_trust), initial];
+	for vector in vectors.iter().flatten() {
+		if vector.len() != c.size() {
+			return Err(CoreError::InvalidParamsError(format!(
+				"vector size {} doesn't match local trust size {}",
+				vector.len(),
+				c.size()
+			)));
+		}
+	}
+	if c.size() == 0 {
+		return Ok(ComputeResult {
+			scores: Vec::new(),
+			iterations: 0,
+			flat_tail: 0,
+			elapsed: Duration::ZERO,
+		});
+	}
+
+	let start = Instant::now();
+	let p = normalize_pre_trust(pre_trust);
+
+	let mut t = match initial {
+		Some(initial) if initial.iter().any(|x| *x > 0.) => normalize_pre_trust(initial),
+		_ => p.clone(),
+	};
+	let mut iterations = 0;
+	let mut flat_tail = 0;
+	// Ranking is only tracked for flat-tail, as sorting costs more than an iteration
+	let is_flat_tail = params.flat_tail > 0;
+	let mut ranking = if is_flat_tail { leaders(&t, params.num_leaders) } else { Vec::new() };
+	// The matrix is split across the same worker threads for every iteration
+	c.with_workers(|mul| loop {
+		let next = iterate(c, mul, &p, &t, params.alpha);
+		let delta: f64 = next.iter().zip(&t).map(|(a, b)| (a - b).abs()).sum();
+		t = next;
+		iterations += 1;
+
+		if is_flat_tail {
+			let next_ranking = leaders(&t, params.num_leaders);
+			flat_tail = if next_ranking == ranking { flat_tail + 1 } else { 0 };
+			ranking = next_ranking;
+		}
+
+		let is_converged = if is_flat_tail {
+			flat_tail >= params.flat_tail
+		} else {
+			delta < params.epsilon
+		};
+		let max_iterations =
+			if params.max_iterations == 0 { MAX_ITERATIONS } else { params.max_iterations };
+		let is_exhausted = iterations >= max_iterations;
+		if is_converged || is_exhausted {
+			break;
+		}
+	});
+
+	Ok(ComputeResult { scores: t, iterations, flat_tail, elapsed: start.elapsed() })
+}
+
+/// Indices of the top peers by score, highest first (ties broken by index).
+fn leaders(t: &[f64], num_leaders: u32) -> Vec<usize> {
+	let mut ranking: Vec<usize> = (0..t.len()).collect();
+	ranking.sort_unstable_by(|a, b| t[*b].total_cmp(&t[*a]).then(a.cmp(b)));
+	if num_leaders > 0 {
+		ranking.truncate(num_leaders as usize);
+	}
+	ranking
+}
+
+fn iterate(
+	c: &CscMatrix, mul: &mut dyn FnMut(&[f64]) -> Vec<f64>, p: &[f64], t: &[f64], alpha: f64,
+) -> Vec<f64> {
+	let mut next = mul(t);
+
+	let dangling: f64 = c.dangling().iter().map(|x| t[*x as usize]).sum();
+	next.iter_mut()
+		.zip(p)
+		.for_each(|(n, p)| *n = (1. - alpha) * (*n + dangling * p) + alpha * p);
+
+	next
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_normalize_pre_trust() {
+		assert_eq!(normalize_pre_trust(&[0., 2., 6.]), vec![0., 0.25, 0.75]);
+		assert_eq!(normalize_pre_trust(&[0., 0.]), vec![0.5, 0.5]);
+	}
+
+	#[test]
+	fn should_converge_to_stationary_distribution() {
+		// 0 -> 1, 1 -> 2, 2 -> 0, 2 -> 1
+		let mut lt = LocalTrust::new(3);
+		lt.set(0, 1, 1.);
+		lt.set(1, 2, 1.);
+		lt.set(2, 0, 1.);
+		lt.set(2, 1, 1.);
+
+		let params = ComputeParams {
+			alpha: 0.,
+			epsilon: 1e-12,
+			max_iterations: 1000,
+			..ComputeParams::default()
+		};
+		let res = eigentrust(&lt, &[1., 1., 1.], None, &params).unwrap();
+
+		let expected = [0.2, 0.4, 0.4];
+		for (score, expected) in res.scores.iter().zip(expected) {
+			assert!((score - expected).abs() < 1e-6);
+		}
+		assert!(res.iterations < 1000);
+	}
+
+	#[test]
+	fn should_only_reach_pre_trusted_through_local_trust() {
+		// Pre-trusted peer 0 trusts 1, while 2 and 3 only trust each other.
+		let mut lt = LocalTrust::new(4);
+		lt.set(0, 1, 1.);
+		lt.set(1, 0, 1.);
+		lt.set(2, 3, 1.);
+		lt.set(3, 2, 1.);
+
+		let res = eigentrust(&lt, &[1., 0., 0., 0.], None, &ComputeParams::default()).unwrap();
+
+		assert!(res.scores[0] > 0. && res.scores[1] > 0.);
+		assert_eq!(res.scores[2], 0.);
+		assert_eq!(res.scores[3], 0.);
+		assert!((res.scores.iter().sum::<f64>() - 1.).abs() < 1e-9);
+	}
+
+	#[test]
+	fn should_stop_at_max_iterations() {
+		let mut lt = LocalTrust::new(2);
+		lt.set(0, 1, 1.);
+		lt.set(1, 0, 1.);
+
+		let params = ComputeParams {
+			alpha: 0.,
+			epsilon: 1e-12,
+			max_iterations: 7,
+			..ComputeParams::default()
+		};
+		let res = eigentrust(&lt, &[1., 0.], None, &params).unwrap();
+		assert_eq!(res.iterations, 7);
+	}
+
+	#[test]
+	fn should_stop_periodic_iteration_without_max_iterations() {
+		// Without pre-trust strength, the trust keeps going around the cycle
+		let mut lt = LocalTrust::new(2);
+		lt.set(0, 1, 1.);
+		lt.set(1, 0, 1.);
+
+		let params = ComputeParams { alpha: 0., ..ComputeParams::default() };
+		let res = eigentrust(&lt, &[1., 0.], None, &params).unwrap();
+		assert_eq!(res.iterations, MAX_ITERATIONS);
+
+		let params = ComputeParams { alpha: 0., flat_tail: 3, ..ComputeParams::default() };
+		let res = eigentrust(&lt, &[1., 0.], None, &params).unwrap();
+		assert_eq!(res.iterations, MAX_ITERATIONS);
+		assert_eq!(res.flat_tail, 0);
+	}
+
+	#[test]
+	fn should_start_from_initial_vector() {
+		let mut lt = LocalTrust::new(2);
+		lt.set(0, 1, 1.);
+		lt.set(1, 0, 1.);
+
+		// With alpha = 0 the iteration just keeps swapping the starting point
+		let params = ComputeParams {
+			alpha: 0.,
+			epsilon: 1e-12,
+			max_iterations: 2,
+			..ComputeParams::default()
+		};
+		let res = eigentrust(&lt, &[1., 1.], Some(&[3., 1.]), &params).unwrap();
+		assert_eq!(res.scores, vec![0.75, 0.25]);
+
+		let res = eigentrust(&lt, &[1., 1.], Some(&[0., 0.]), &params).unwrap();
+		assert_eq!(res.scores, vec![0.5, 0.5]);
+	}
+
+	#[test]
+	fn should_converge_on_flat_tail() {
+		// 0 -> 1, 1 -> 2, 2 -> 0, 2 -> 1
+		let mut lt = LocalTrust::new(3);
+		lt.set(0, 1, 1.);
+		lt.set(1, 2, 1.);
+		lt.set(2, 0, 1.);
+		lt.set(2, 1, 1.);
+
+		// The ranking settles long before the scores get within epsilon
+		let params = ComputeParams { epsilon: 1e-15, flat_tail: 3, ..ComputeParams::default() };
+		let res = eigentrust(&lt, &[1., 1., 1.], None, &params).unwrap();
+		assert_eq!(res.flat_tail, 3);
+
+		let params = ComputeParams { epsilon: 1e-15, ..ComputeParams::default() };
+		let full = eigentrust(&lt, &[1., 1., 1.], None, &params).unwrap();
+		assert!(res.iterations < full.iterations);
+		assert_eq!(full.flat_tail, 0);
+
+		assert_eq!(leaders(&full.scores, 1), vec![1]);
+	}
+
+	#[test]
+	#[ignore = "performance check, run with --release --ignored"]
+	fn should_handle_million_edges_within_seconds() {
+		let size = 100_000;
+		let mut lt = LocalTrust::new(size);
+		for x in 0..size as u32 {
+			for k in 1..=10 {
+				lt.set(
+					x,
+					(x.wrapping_mul(2_654_435_761) ^ (k * 40_503)) % size as u32,
+					f64::from(k),
+				);
+			}
+		}
+		let pre_trust: Vec<f64> = (0..size).map(|x| if x % 100 == 0 { 1. } else { 0. }).collect();
+
+		let res = eigentrust(&lt, &pre_trust, None, &ComputeParams::default()).unwrap();
+		assert!(res.elapsed < Duration::from_secs(10));
+		assert!(res.iterations_per_second() > 0.);
+	}
+
+	#[test]
+	fn should_reject_invalid_params() {
+		let lt = LocalTrust::new(1);
+		let params = ComputeParams { alpha: 1.5, ..ComputeParams::default() };
+		assert!(eigentrust(&lt, &[1.], None, &params).is_err());
+		assert!(eigentrust(&lt, &[1., 1.], None, &ComputeParams::default()).is_err());
+	}
+}
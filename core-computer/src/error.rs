@@ -0,0 +1,33 @@
+use mm_spd_vc::error::VcError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CoreError {
+	#[error("GrpcError: {0}")]
+	GrpcError(tonic::Status),
+
+	#[error("IoError: {0}")]
+	IoError(std::io::Error),
+
+	#[error("VcError: {0}")]
+	VcError(VcError),
+
+	#[error("InvalidParamsError: {0}")]
+	InvalidParamsError(String),
+
+	#[error("NotFoundError: {0}")]
+	NotFoundError(String),
+
+	#[error("ParseError")]
+	ParseError,
+}
+
+impl From<CoreError> for tonic::Status {
+	fn from(value: CoreError) -> Self {
+		match value {
+			CoreError::InvalidParamsError(_) => Self::invalid_argument(value.to_string()),
+			CoreError::NotFoundError(_) => Self::not_found(value.to_string()),
+			_ => Self::internal(format!("Internal error: {}", value)),
+		}
+	}
+}
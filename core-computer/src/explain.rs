@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use snap_score_computer::badge::UserBadge;
+use snap_score_computer::explain::{top_contributions, Contribution, Explanation};
+use snap_score_computer::score::Edge;
+
+use crate::matrix::LocalTrust;
+
+/// Explain the peer scores: the top `n` trusters and distrusters of each peer,
+/// by their share of the trust the peer received or lost, i.e. `T+(i) * c_ij`
+/// (and `T+(i) * d_ij`) over the peer's total.
+/// The opinion is the normalized local trust `c_ij`, negative for distrust.
+/// Only the opinions of peers with a positive standing count, as for the scores.
+pub fn explain_peers(
+	mapping: &BTreeMap<u32, String>, trust: &LocalTrust, distrust: &LocalTrust, positive: &[f64],
+	n: usize,
+) -> BTreeMap<String, Explanation> {
+	let (trust, distrust) = (trust.normalize(), distrust.normalize());
+	let trust_entries = trust.entries();
+	let distrust_entries = distrust.entries().map(|(x, y, value)| (x, y, -value));
+
+	let mut contributions: BTreeMap<u32, Vec<Contribution>> = BTreeMap::new();
+	for (x, y, opinion) in trust_entries.chain(distrust_entries) {
+		let standing = positive.get(x as usize).copied().unwrap_or(0.);
+		let Some(peer) = mapping.get(&x).filter(|_| standing > 0.) else {
+			continue;
+		};
+		contributions.entry(y).or_default().push(Contribution {
+			peer: peer.clone(),
+			trust: standing,
+			opinion,
+			share: standing * opinion.abs(),
+		});
+	}
+
+	contributions
+		.into_iter()
+		.filter_map(|(y, mut contributions)| {
+			let total: f64 = contributions.iter().map(|x| x.share).sum();
+			contributions.iter_mut().for_each(|x| x.share /= total);
+			let explanation = Explanation {
+				contributions: top_contributions(contributions, n),
+				auditors: Vec::new(),
+			};
+			Some((mapping.get(&y)?.clone(), explanation))
+		})
+		.collect()
+}
+
+/// Peers behind the user badges: the auditors who distrust a Reported user,
+/// or the pre-trusted peers who endorse a Highly Trusted one.
+pub fn badge_auditors(
+	users: &BTreeMap<String, UserBadge>, auditors: &BTreeSet<String>,
+	pre_trust: &HashMap<String, f64>, trust: &[Edge], distrust: &[Edge],
+) -> BTreeMap<String, Vec<String>> {
+	let mut badge_auditors: BTreeMap<String, Vec<String>> = BTreeMap::new();
+	let edges = trust.iter().map(|x| (x, UserBadge::HighlyTrusted));
+	let edges = edges.chain(distrust.iter().map(|x| (x, UserBadge::Reported)));
+	for ((truster, trustee, value), badge) in edges {
+		if *value <= 0. || truster == trustee || users.get(trustee) != Some(&badge) {
+			continue;
+		}
+		let is_behind = match badge {
+			UserBadge::HighlyTrusted => pre_trust.get(truster).map_or(false, |x| *x > 0.),
+			UserBadge::Reported => auditors.contains(truster),
+		};
+		if is_behind {
+			badge_auditors.entry(trustee.clone()).or_default().push(truster.clone());
+		}
+	}
+	badge_auditors
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_explain_peer_scores() {
+		let mapping: BTreeMap<u32, String> = ["alice", "bob", "carol"]
+			.iter()
+			.enumerate()
+			.map(|(i, x)| (i as u32, x.to_string()))
+			.collect();
+		let mut trust = LocalTrust::new(3);
+		trust.set(0, 2, 1.);
+		trust.set(0, 1, 1.);
+		trust.set(1, 2, 1.);
+		let mut distrust = LocalTrust::new(3);
+		distrust.set(1, 2, 1.);
+		// Zero standing, so the opinion doesn't count
+		distrust.set(2, 0, 1.);
+
+		let explanations = explain_peers(&mapping, &trust, &distrust, &[0.5, 0.25, 0.], 2);
+		let carol = &explanations["carol"].contributions;
+		// alice: 0.5 * 0.5, bob: 0.25 * 1 (trust) and 0.25 * -1 (distrust)
+		assert_eq!(carol.len(), 2);
+		assert_eq!(carol[0].peer, "alice");
+		assert_eq!(carol[0].opinion, 0.5);
+		assert_eq!(carol[0].share, 1. / 3.);
+		assert!(!explanations.contains_key("alice"));
+	}
+
+	#[test]
+	fn should_find_auditors_behind_badges() {
+		let edge = |x: &str, y: &str| (x.to_string(), y.to_string(), 1.);
+		let users = BTreeMap::from([
+			("bob".to_string(), UserBadge::HighlyTrusted),
+			("carol".to_string(), UserBadge::Reported),
+		]);
+		let auditors = BTreeSet::from(["bob".to_string()]);
+		let pre_trust = HashMap::from([("alice".to_string(), 1.)]);
+		let trust = vec![edge("alice", "bob"), edge("alice", "carol")];
+		let distrust = vec![edge("bob", "carol"), edge("alice", "bob")];
+
+		let res = badge_auditors(&users, &auditors, &pre_trust, &trust, &distrust);
+		assert_eq!(res["bob"], vec!["alice".to_string()]);
+		assert_eq!(res["carol"], vec!["bob".to_string()]);
+	}
+}
@@ -0,0 +1,14 @@
+pub mod combiner;
+pub mod distrust;
+pub mod domain;
+pub mod eigentrust;
+pub mod error;
+pub mod explain;
+pub mod matrix;
+pub mod pipeline;
+pub mod pretrust;
+pub mod publisher;
+pub mod service;
+pub mod store;
+pub mod sync;
+pub mod timestamp;
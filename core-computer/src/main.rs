@@ -1,3 +1,149 @@
-fn main() {
-	println!("Hello, world!");
+use std::env;
+use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use secp256k1::SecretKey;
+
+use tonic::transport::{Channel, Server};
+
+use proto_buf::eigentrust::compute_server::ComputeServer;
+
+use core_compute::combiner::Combiner;
+use core_compute::domain::DomainConfig;
+use core_compute::pretrust::load_file;
+use core_compute::publisher::Publisher;
+use core_compute::service::ComputeService;
+use core_compute::store::Store;
+use core_compute::sync::{sync, DomainRun};
+use core_compute::timestamp::Timestamp;
+
+const COMPUTE_INTERVAL: Duration = Duration::from_secs(10);
+
+async fn compute(
+	run: &mut DomainRun, epoch: u64, store: &Mutex<Store>, publisher: Option<&Publisher>,
+) -> Result<(), Box<dyn Error>> {
+	let DomainRun { config, pipeline, .. } = run;
+
+	// Use the pre-trust set valid at the epoch
+	let pre_trust = {
+		let store = store.lock().map_err(|_| "poisoned store lock")?;
+		store.get_pre_trust(config.domain)?.at(Timestamp::new(u128::from(epoch))).cloned()
+	};
+	let res = pipeline.compute(pre_trust.as_ref(), &config.params)?;
+	println!(
+		"{}: converged after {} iterations ({:.1} it/s), flat tail {}",
+		config.scope, res.iterations, res.iterations_per_second, res.flat_tail
+	);
+
+	for peer in &res.peers {
+		println!("{}: T+ {} T {}", peer.did, peer.positive, peer.adjusted);
+	}
+	for (snap, (score, badge)) in &res.snaps {
+		println!(
+			"{}: R_c {} C {} {:?}",
+			snap, score.value, score.confidence, badge
+		);
+	}
+	for (did, badge) in &res.users {
+		println!("{}: {:?}", did, badge);
+	}
+	store
+		.lock()
+		.map_err(|_| "poisoned store lock")?
+		.set_explanations(config.domain, res.explanations.clone());
+
+	if let Some(publisher) = publisher {
+		let peers = publisher.peer_credentials(epoch, &config.scope, &res.peers)?;
+		let snaps = publisher.snap_credentials(epoch, &config.scope, &res.snaps)?;
+		let threshold = res.trust_threshold.unwrap_or(0.);
+		publisher.publish(epoch, &config.scope, &peers, &snaps, threshold)?;
+		println!(
+			"Published {} epoch {} as {}",
+			config.scope,
+			epoch,
+			publisher.issuer()
+		);
+	}
+
+	Ok(())
+}
+
+/// Periodically recompute every domain from the linear combiner,
+/// warm-starting each from its previous result.
+async fn compute_from_combiner(
+	domains: Vec<DomainConfig>, store: Arc<Mutex<Store>>, publisher: Option<Publisher>,
+) -> Result<(), Box<dyn Error>> {
+	let lc_channel = Channel::from_static("http://[::1]:50052").connect().await?;
+	let mut combiner = Combiner::new(lc_channel);
+	let mut runs: Vec<DomainRun> = domains.into_iter().map(DomainRun::new).collect();
+
+	let mut is_initial = true;
+	let mut interval = tokio::time::interval(COMPUTE_INTERVAL);
+	loop {
+		interval.tick().await;
+		match sync(&mut combiner, &mut runs, is_initial).await {
+			Ok(false) => continue,
+			Ok(true) => is_initial = false,
+			Err(e) => {
+				println!("Failed to sync with the linear combiner: {}", e);
+				continue;
+			},
+		}
+
+		// All the domains share the epoch
+		let epoch = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+		for run in runs.iter_mut().filter(|x| x.is_changed) {
+			match compute(run, epoch, &store, publisher.as_ref()).await {
+				Ok(()) => run.is_changed = false,
+				Err(e) => println!("Failed to compute {}: {}", run.config.scope, e),
+			}
+		}
+	}
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+	let domains = DomainConfig::from_env()?;
+
+	// Pre-trust is loaded from `<dir>/<domain>.csv` if present,
+	// and can be updated through `UpdateTrustVector` on the `pre-trust-<domain>` vector,
+	// timestamped in seconds since the unix epoch
+	let mut store = Store::default();
+	let pre_trust_dir =
+		PathBuf::from(env::var("CORE_COMPUTER_PRE_TRUST_DIR").unwrap_or("./pre-trust".to_string()));
+	for config in &domains {
+		let pre_trust_id = store.create_pre_trust(config.domain);
+		let path = pre_trust_dir.join(format!("{}.csv", config.domain));
+		if path.exists() {
+			let entries = load_file(&path)?;
+			store.update_vector(&pre_trust_id, Timestamp::default(), entries)?;
+		}
+	}
+	let store = Arc::new(Mutex::new(store));
+
+	let addr = "[::1]:50053".parse()?;
+	let service = ComputeService::new(store.clone());
+	let server =
+		tokio::spawn(Server::builder().add_service(ComputeServer::new(service)).serve(addr));
+
+	// Results are only published when an issuer key is configured
+	let publisher = match env::var("CORE_COMPUTER_ISSUER_KEY") {
+		Ok(key) => {
+			let output_dir =
+				env::var("CORE_COMPUTER_OUTPUT_DIR").unwrap_or("./spd_scores".to_string());
+			let secret_key = SecretKey::from_str(key.trim_start_matches("0x"))?;
+			Some(Publisher::new(secret_key, PathBuf::from(output_dir)))
+		},
+		Err(_) => None,
+	};
+
+	if let Err(e) = compute_from_combiner(domains, store, publisher).await {
+		println!("Failed to compute from the linear combiner: {}", e);
+	}
+
+	server.await??;
+	Ok(())
 }
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+/// Below this many entries, the per-thread overhead outweighs the gain of splitting the work.
+const PARALLEL_THRESHOLD: usize = 10_000;
+
+/// Sparse local trust matrix.
+/// Rows are trusters and columns are trustees, both addressed by their combiner index.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocalTrust {
+	size: usize,
+	rows: BTreeMap<u32, BTreeMap<u32, f64>>,
+}
+
+impl LocalTrust {
+	pub fn new(size: usize) -> Self {
+		Self { size, rows: BTreeMap::new() }
+	}
+
+	/// Number of peers (rows and columns) of the matrix.
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Set the value of the (x, y) entry, growing the matrix if needed.
+	/// Setting a zero value removes the entry.
+	pub fn set(&mut self, x: u32, y: u32, value: f64) {
+		let dim = usize::try_from(x.max(y)).unwrap_or(usize::MAX).saturating_add(1);
+		self.size = self.size.max(dim);
+
+		if value == 0. {
+			if let Some(row) = self.rows.get_mut(&x) {
+				row.remove(&y);
+				if row.is_empty() {
+					self.rows.remove(&x);
+				}
+			}
+			return;
+		}
+		self.rows.entry(x).or_default().insert(y, value);
+	}
+
+	pub fn get(&self, x: u32, y: u32) -> f64 {
+		self.rows.get(&x).and_then(|row| row.get(&y)).copied().unwrap_or(0.)
+	}
+
+	/// Iterate over the non-zero entries, ordered by truster then trustee.
+	pub fn entries(&self) -> impl Iterator<Item = (u32, u32, f64)> + '_ {
+		self.rows.iter().flat_map(|(x, row)| row.iter().map(move |(y, value)| (*x, *y, *value)))
+	}
+
+	/// Keep only the entries for which the predicate holds.
+	pub fn retain<F: Fn(u32, u32) -> bool>(&mut self, f: F) {
+		self.rows.iter_mut().for_each(|(x, row)| row.retain(|y, _| f(*x, *y)));
+		self.rows.retain(|_, row| !row.is_empty());
+	}
+
+	/// Row-normalized copy of the matrix.
+	/// Self-trust and non-positive entries are dropped,
+	/// and rows left without any outgoing trust (dangling peers) are omitted.
+	pub fn normalize(&self) -> Self {
+		let mut rows = BTreeMap::new();
+		for (x, row) in &self.rows {
+			let outgoing: BTreeMap<u32, f64> = row
+				.iter()
+				.filter(|(y, value)| *y != x && **value > 0.)
+				.map(|(y, v)| (*y, *v))
+				.collect();
+			let sum: f64 = outgoing.values().sum();
+			if sum <= 0. {
+				continue;
+			}
+			let normalized = outgoing.into_iter().map(|(y, value)| (y, value / sum)).collect();
+			rows.insert(*x, normalized);
+		}
+		Self { size: self.size, rows }
+	}
+
+	/// Whether the peer has any outgoing trust.
+	pub fn has_row(&self, x: u32) -> bool {
+		self.rows.contains_key(&x)
+	}
+}
+
+/// Compressed sparse column (CSC) form of a local trust matrix,
+/// so that `C^T * t` can be computed one trustee (column) at a time, independently of each other.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CscMatrix {
+	size: usize,
+	/// Column `y` spans `rows[col_ptrs[y]..col_ptrs[y + 1]]`.
+	col_ptrs: Vec<usize>,
+	rows: Vec<u32>,
+	values: Vec<f64>,
+	/// Trusters without any outgoing trust.
+	dangling: Vec<u32>,
+}
+
+impl From<&LocalTrust> for CscMatrix {
+	fn from(lt: &LocalTrust) -> Self {
+		let mut counts = vec![0; lt.size()];
+		lt.entries().for_each(|(_, y, _)| counts[y as usize] += 1);
+
+		let mut col_ptrs = Vec::with_capacity(lt.size() + 1);
+		col_ptrs.push(0);
+		for count in counts {
+			col_ptrs.push(col_ptrs[col_ptrs.len() - 1] + count);
+		}
+
+		let nnz = col_ptrs[lt.size()];
+		let mut rows = vec![0; nnz];
+		let mut values = vec![0.; nnz];
+		let mut next = col_ptrs.clone();
+		for (x, y, value) in lt.entries() {
+			let i = next[y as usize];
+			rows[i] = x;
+			values[i] = value;
+			next[y as usize] += 1;
+		}
+
+		let dangling = (0..lt.size() as u32).filter(|x| !lt.has_row(*x)).collect();
+		Self { size: lt.size(), col_ptrs, rows, values, dangling }
+	}
+}
+
+impl CscMatrix {
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	pub fn dangling(&self) -> &[u32] {
+		&self.dangling
+	}
+
+	fn column_product(&self, y: usize, t: &[f64]) -> f64 {
+		let range = self.col_ptrs[y]..self.col_ptrs[y + 1];
+		self.rows[range.clone()]
+			.iter()
+			.zip(&self.values[range])
+			.map(|(x, value)| value * t[*x as usize])
+			.sum()
+	}
+
+	/// Compute `C^T * t`, splitting the columns across the available threads.
+	pub fn mul_transpose(&self, t: &[f64]) -> Vec<f64> {
+		self.with_workers(|mul| mul(t))
+	}
+
+	/// Run `f` with a function computing `C^T * t`, for as many vectors as needed.
+	/// The columns are split across worker threads, started once for all the products.
+	pub fn with_workers<R>(&self, f: impl FnOnce(&mut dyn FnMut(&[f64]) -> Vec<f64>) -> R) -> R {
+		let threads = thread::available_parallelism().map_or(1, |x| x.get());
+		if threads == 1 || self.values.len() < PARALLEL_THRESHOLD {
+			let mut mul = |t: &[f64]| (0..self.size).map(|y| self.column_product(y, t)).collect();
+			return f(&mut mul);
+		}
+
+		let chunk_size = (self.size + threads - 1) / threads;
+		thread::scope(|s| {
+			let (res_tx, res_rx) = mpsc::channel();
+			let mut senders = Vec::new();
+			for start in (0..self.size).step_by(chunk_size) {
+				let (tx, rx) = mpsc::channel::<Arc<[f64]>>();
+				let res_tx = res_tx.clone();
+				let columns = start..(start + chunk_size).min(self.size);
+				s.spawn(move || {
+					// Until the senders are dropped, once `f` returns
+					for t in rx {
+						let chunk: Vec<f64> =
+							columns.clone().map(|y| self.column_product(y, &t)).collect();
+						if res_tx.send((columns.start, chunk)).is_err() {
+							break;
+						}
+					}
+				});
+				senders.push(tx);
+			}
+
+			let mut mul = |t: &[f64]| {
+				let t: Arc<[f64]> = Arc::from(t);
+				for tx in &senders {
+					tx.send(t.clone()).expect("worker thread exited");
+				}
+				let mut res = vec![0.; self.size];
+				for _ in 0..senders.len() {
+					let (start, chunk) = res_rx.recv().expect("worker thread exited");
+					res[start..start + chunk.len()].copy_from_slice(&chunk);
+				}
+				res
+			};
+			f(&mut mul)
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_set_and_grow() {
+		let mut lt = LocalTrust::new(2);
+		lt.set(0, 1, 5.);
+		lt.set(3, 0, 2.);
+		assert_eq!(lt.size(), 4);
+		assert_eq!(lt.get(0, 1), 5.);
+		assert_eq!(lt.get(3, 0), 2.);
+
+		lt.set(0, 1, 0.);
+		assert_eq!(lt.entries().collect::<Vec<_>>(), vec![(3, 0, 2.)]);
+	}
+
+	#[test]
+	fn should_normalize_rows() {
+		let mut lt = LocalTrust::new(3);
+		lt.set(0, 1, 30.);
+		lt.set(0, 2, 10.);
+		lt.set(0, 0, 50.);
+		lt.set(1, 1, 10.);
+		lt.set(2, 0, 5.);
+
+		let normalized = lt.normalize();
+		assert_eq!(
+			normalized.entries().collect::<Vec<_>>(),
+			vec![(0, 1, 0.75), (0, 2, 0.25), (2, 0, 1.)]
+		);
+		assert!(!normalized.has_row(1));
+	}
+
+	#[test]
+	fn should_multiply_transposed_csc() {
+		// Large enough to be split across threads
+		let size = 5_000;
+		let mut lt = LocalTrust::new(size);
+		for x in 0..size as u32 {
+			for k in 1..4 {
+				lt.set(x, (x * 7 + k * 13) % size as u32, f64::from(k));
+			}
+		}
+		lt.set(4, 4, 0.);
+		let lt = lt.normalize();
+
+		let t: Vec<f64> = (0..size).map(|x| x as f64 / size as f64).collect();
+		let mut expected = vec![0.; size];
+		lt.entries().for_each(|(x, y, value)| expected[y as usize] += value * t[x as usize]);
+
+		let csc = CscMatrix::from(&lt);
+		let res = csc.mul_transpose(&t);
+		for (a, b) in res.iter().zip(&expected) {
+			assert!((a - b).abs() < 1e-12);
+		}
+		assert!(csc.dangling().is_empty());
+
+		// The same workers compute consecutive products
+		let (first, second) = csc.with_workers(|mul| (mul(&t), mul(&expected)));
+		assert_eq!(first, res);
+		let mut twice = vec![0.; size];
+		lt.entries().for_each(|(x, y, value)| twice[y as usize] += value * expected[x as usize]);
+		for (a, b) in second.iter().zip(&twice) {
+			assert!((a - b).abs() < 1e-12);
+		}
+	}
+}
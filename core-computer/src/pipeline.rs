@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, HashMap};
+
+use snap_score_computer::badge::{
+	highly_trusted_auditors, snap_badges, user_badges, weakest_auditor_score, SnapBadge, UserBadge,
+};
+use snap_score_computer::explain::{explain_snaps, Explanation};
+use snap_score_computer::score::{self, Edge, SnapScore};
+
+use crate::combiner::is_peer;
+use crate::distrust::{adjust, PeerScores};
+use crate::eigentrust::{eigentrust_csc, ComputeParams, ComputeResult};
+use crate::error::CoreError;
+use crate::explain::{badge_auditors, explain_peers};
+use crate::matrix::{CscMatrix, LocalTrust};
+use crate::pretrust::PreTrustSet;
+use crate::publisher::PeerResult;
+
+/// Number of top contributors kept in the explanation of a score.
+const EXPLANATION_SIZE: usize = 10;
+
+/// Results of a full (Phase 1 and 2) compute.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PipelineResult {
+	pub iterations: u32,
+	pub iterations_per_second: f64,
+	pub flat_tail: u32,
+	pub peers: Vec<PeerResult>,
+	pub snaps: BTreeMap<String, (SnapScore, SnapBadge)>,
+	pub users: BTreeMap<String, UserBadge>,
+	/// `T+(d)` of the weakest highly trusted auditor.
+	pub trust_threshold: Option<f64>,
+	/// Explanations of the peer and snap scores, by DID.
+	pub explanations: BTreeMap<String, Explanation>,
+}
+
+/// Inputs of a domain, kept in memory between computes,
+/// so that only the changes have to be applied before the next one.
+/// The global trust of the previous compute is the starting point of the next one.
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+	mapping: BTreeMap<u32, String>,
+	trust: LocalTrust,
+	distrust: LocalTrust,
+	global_trust: Vec<f64>,
+	/// Normalized peer-to-peer local trust, as iterated on,
+	/// until the trust or the participants change.
+	peer_trust: Option<CscMatrix>,
+}
+
+impl Pipeline {
+	/// Number of participants (peers and snaps).
+	pub fn size(&self) -> u32 {
+		self.mapping.keys().last().map_or(0, |x| x + 1)
+	}
+
+	pub fn trust(&self) -> &LocalTrust {
+		&self.trust
+	}
+
+	pub fn distrust(&self) -> &LocalTrust {
+		&self.distrust
+	}
+
+	pub fn set_mapping(&mut self, mapping: BTreeMap<u32, String>) {
+		if mapping != self.mapping {
+			self.peer_trust = None;
+		}
+		self.mapping = mapping;
+	}
+
+	/// Set the given local trust entries, overriding the previous values.
+	pub fn update_trust(&mut self, entries: Vec<(u32, u32, f64)>) {
+		if !entries.is_empty() {
+			self.peer_trust = None;
+		}
+		entries.into_iter().for_each(|(x, y, value)| self.trust.set(x, y, value));
+	}
+
+	/// Set the given local distrust entries, overriding the previous values.
+	pub fn update_distrust(&mut self, entries: Vec<(u32, u32, f64)>) {
+		entries.into_iter().for_each(|(x, y, value)| self.distrust.set(x, y, value));
+	}
+
+	fn peers(&self) -> Vec<bool> {
+		(0..self.size()).map(|i| self.mapping.get(&i).map_or(false, |x| is_peer(x))).collect()
+	}
+
+	/// Phase 1: peer scores.
+	/// Snaps don't take part in peer-to-peer trust, they are scored separately.
+	fn compute_peers(
+		&mut self, pre_trust: &[f64], params: &ComputeParams,
+	) -> Result<(PeerScores, ComputeResult), CoreError> {
+		let peers = self.peers();
+		let distrust = peer_matrix(&self.distrust, &peers);
+		let trust = &self.trust;
+		let c = self
+			.peer_trust
+			.get_or_insert_with(|| CscMatrix::from(&peer_matrix(trust, &peers).normalize()));
+
+		self.global_trust.resize(peers.len(), 0.);
+		let res = eigentrust_csc(c, pre_trust, Some(&self.global_trust), params)?;
+		self.global_trust = res.scores.clone();
+
+		let scores = adjust(&res.scores, &distrust)?;
+		Ok((scores, res))
+	}
+
+	/// Pre-trust indexed by participant.
+	/// Falls back to uniform pre-trust over the peers if none of them is pre-trusted.
+	fn pre_trust(&self, peers: &[bool], set: Option<&PreTrustSet>) -> Vec<f64> {
+		let weight = |i: usize| {
+			let did = self.mapping.get(&(i as u32))?;
+			set?.get(did).copied()
+		};
+		let pre_trust: Vec<f64> =
+			(0..peers.len()).map(|i| if peers[i] { weight(i).unwrap_or(0.) } else { 0. }).collect();
+		if pre_trust.iter().any(|x| *x > 0.) {
+			return pre_trust;
+		}
+		peers.iter().map(|x| if *x { 1. } else { 0. }).collect()
+	}
+
+	/// Run Phase 1 (peer scores) and Phase 2 (snap scores and badges)
+	/// over the current inputs, with the given pre-trust set.
+	pub fn compute(
+		&mut self, pre_trust: Option<&PreTrustSet>, params: &ComputeParams,
+	) -> Result<PipelineResult, CoreError> {
+		let peers = self.peers();
+		let pre_trust = self.pre_trust(&peers, pre_trust);
+		let (scores, res) = self.compute_peers(&pre_trust, params)?;
+
+		let trust = to_edges(&self.trust, &self.mapping);
+		let distrust = to_edges(&self.distrust, &self.mapping);
+		let pre_trust = to_scores(&pre_trust, &self.mapping);
+		let positive = to_scores(&scores.positive, &self.mapping);
+		let adjusted = to_scores(&scores.adjusted, &self.mapping);
+
+		let auditors = highly_trusted_auditors(&trust, &pre_trust);
+		let trust_threshold = weakest_auditor_score(&auditors, &positive);
+		let snap_scores = score::compute(&adjusted, &trust, &distrust);
+		let snaps = snap_badges(&snap_scores, trust_threshold)
+			.into_iter()
+			.map(|(snap, badge)| {
+				let score = snap_scores[&snap].clone();
+				(snap, (score, badge))
+			})
+			.collect();
+		let users = user_badges(&auditors, &distrust);
+
+		let mut explanations =
+			explain_snaps(&adjusted, &trust, &distrust, &auditors, EXPLANATION_SIZE);
+		let mut badge_auditors = badge_auditors(&users, &auditors, &pre_trust, &trust, &distrust);
+		let peer_explanations = explain_peers(
+			&self.mapping,
+			&peer_matrix(&self.trust, &peers),
+			&peer_matrix(&self.distrust, &peers),
+			&scores.positive,
+			EXPLANATION_SIZE,
+		);
+		for (did, mut explanation) in peer_explanations {
+			explanation.auditors = badge_auditors.remove(&did).unwrap_or_default();
+			explanations.insert(did, explanation);
+		}
+		for (did, auditors) in badge_auditors {
+			explanations.insert(did, Explanation { contributions: Vec::new(), auditors });
+		}
+
+		let peers = self
+			.mapping
+			.iter()
+			.filter(|(i, _)| peers[**i as usize])
+			.map(|(i, did)| PeerResult {
+				did: did.clone(),
+				positive: scores.positive[*i as usize],
+				adjusted: scores.adjusted[*i as usize],
+			})
+			.collect();
+
+		Ok(PipelineResult {
+			iterations: res.iterations,
+			iterations_per_second: res.iterations_per_second(),
+			flat_tail: res.flat_tail,
+			peers,
+			snaps,
+			users,
+			trust_threshold,
+			explanations,
+		})
+	}
+}
+
+/// Peer-to-peer part of the local trust, sized to the number of participants.
+fn peer_matrix(lt: &LocalTrust, peers: &[bool]) -> LocalTrust {
+	let is_peer = |x: u32| peers.get(x as usize).copied().unwrap_or(false);
+	let mut peer_lt = LocalTrust::new(peers.len());
+	lt.entries()
+		.filter(|(x, y, _)| is_peer(*x) && is_peer(*y))
+		.for_each(|(x, y, value)| peer_lt.set(x, y, value));
+	peer_lt
+}
+
+fn to_edges(lt: &LocalTrust, mapping: &BTreeMap<u32, String>) -> Vec<Edge> {
+	lt.entries()
+		.filter_map(|(x, y, value)| {
+			Some((mapping.get(&x)?.clone(), mapping.get(&y)?.clone(), value))
+		})
+		.collect()
+}
+
+fn to_scores(scores: &[f64], mapping: &BTreeMap<u32, String>) -> HashMap<String, f64> {
+	mapping.iter().map(|(i, did)| (did.clone(), scores[*i as usize])).collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn new_pipeline() -> Pipeline {
+		let dids = ["alice", "bob", "carol", "snap://a"];
+		let mut pipeline = Pipeline::default();
+		pipeline
+			.set_mapping(dids.iter().enumerate().map(|(i, x)| (i as u32, x.to_string())).collect());
+		pipeline.update_trust(vec![
+			(0, 1, 50.),
+			(1, 2, 50.),
+			(2, 0, 50.),
+			(2, 1, 50.),
+			(0, 3, 50.),
+		]);
+		pipeline.update_distrust(vec![(1, 3, 50.)]);
+		pipeline
+	}
+
+	#[test]
+	fn should_warm_start_from_previous_result() {
+		let params = ComputeParams { epsilon: 1e-12, ..ComputeParams::default() };
+		let mut pipeline = new_pipeline();
+		let cold = pipeline.compute(None, &params).unwrap();
+		assert_eq!(cold.peers.len(), 3);
+		assert!(cold.snaps.contains_key("snap://a"));
+
+		// A small delta
+		pipeline.update_trust(vec![(0, 2, 5.)]);
+		let warm = pipeline.compute(None, &params).unwrap();
+
+		let mut fresh = new_pipeline();
+		fresh.update_trust(vec![(0, 2, 5.)]);
+		let expected = fresh.compute(None, &params).unwrap();
+
+		assert!(warm.iterations < expected.iterations);
+		for (a, b) in warm.peers.iter().zip(&expected.peers) {
+			assert_eq!(a.did, b.did);
+			assert!((a.adjusted - b.adjusted).abs() < 1e-9);
+		}
+	}
+
+	#[test]
+	fn should_keep_normalized_trust_until_updated() {
+		let params = ComputeParams::default();
+		let mut pipeline = new_pipeline();
+		pipeline.compute(None, &params).unwrap();
+		let cached = pipeline.peer_trust.clone().unwrap();
+
+		// Distrust and an unchanged mapping don't affect the iterated matrix
+		pipeline.update_distrust(vec![(0, 1, 10.)]);
+		pipeline.set_mapping(pipeline.mapping.clone());
+		assert_eq!(pipeline.peer_trust.as_ref(), Some(&cached));
+
+		pipeline.update_trust(vec![(0, 2, 5.)]);
+		assert!(pipeline.peer_trust.is_none());
+		pipeline.compute(None, &params).unwrap();
+		let expected =
+			CscMatrix::from(&peer_matrix(&pipeline.trust, &pipeline.peers()).normalize());
+		assert_eq!(pipeline.peer_trust, Some(expected));
+	}
+
+	#[test]
+	fn should_grow_with_mapping() {
+		let params = ComputeParams::default();
+		let mut pipeline = new_pipeline();
+		pipeline.compute(None, &params).unwrap();
+
+		let mut mapping = pipeline.mapping.clone();
+		mapping.insert(4, "dave".to_string());
+		pipeline.set_mapping(mapping);
+		pipeline.update_trust(vec![(4, 0, 50.)]);
+
+		let res = pipeline.compute(None, &params).unwrap();
+		assert_eq!(res.peers.len(), 4);
+	}
+
+	#[test]
+	fn should_use_pre_trust_set() {
+		let params = ComputeParams::default();
+		let mut pipeline = new_pipeline();
+		// Snaps can't be pre-trusted
+		let set = PreTrustSet::from([("alice".to_string(), 1.), ("snap://a".to_string(), 1.)]);
+		let res = pipeline.compute(Some(&set), &params).unwrap();
+
+		// bob is the only peer endorsed by the pre-trusted alice
+		assert_eq!(res.users.get("bob"), Some(&UserBadge::HighlyTrusted));
+		assert_eq!(res.users.len(), 1);
+		assert_eq!(res.explanations["bob"].auditors, vec!["alice".to_string()]);
+		// alice endorsed the snap, which bob, the only auditor, reported
+		let snap = &res.explanations["snap://a"];
+		let opinion =
+			|did: &str| snap.contributions.iter().find(|x| x.peer == did).unwrap().opinion;
+		assert_eq!(opinion("alice"), 1.);
+		assert_eq!(opinion("bob"), 0.);
+		assert_eq!(snap.auditors, vec!["bob".to_string()]);
+	}
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::CoreError;
+use crate::timestamp::Timestamp;
+
+/// Normalized pre-trust set, keyed by DID.
+pub type PreTrustSet = BTreeMap<String, f64>;
+
+/// Normalize the pre-trust weights so that they sum up to 1.
+/// Non-positive weights are dropped.
+pub fn normalize(entries: Vec<(String, f64)>) -> PreTrustSet {
+	let mut set = PreTrustSet::new();
+	for (did, weight) in entries.into_iter().filter(|(_, weight)| *weight > 0.) {
+		*set.entry(did).or_default() += weight;
+	}
+	let sum: f64 = set.values().sum();
+	set.values_mut().for_each(|x| *x /= sum);
+	set
+}
+
+/// Read a pre-trust file, holding a `did;weight` line per pre-trusted peer.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn load_file(path: &Path) -> Result<Vec<(String, f64)>, CoreError> {
+	let content = fs::read_to_string(path).map_err(CoreError::IoError)?;
+	content
+		.lines()
+		.map(str::trim)
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.map(|line| {
+			let (did, weight) = line.split_once(';').ok_or(CoreError::ParseError)?;
+			let weight = weight.trim().parse::<f64>().map_err(|_| CoreError::ParseError)?;
+			Ok((did.trim().to_string(), weight))
+		})
+		.collect()
+}
+
+/// Versions of the pre-trust set of a domain.
+/// Each version is valid from its timestamp until the next one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PreTrust {
+	versions: BTreeMap<Timestamp, PreTrustSet>,
+}
+
+impl PreTrust {
+	/// Record a new version, replacing the one with the same timestamp if any.
+	pub fn insert(&mut self, timestamp: Timestamp, entries: Vec<(String, f64)>) {
+		self.versions.insert(timestamp, normalize(entries));
+	}
+
+	/// The set valid at the given timestamp, i.e. the latest version not after it.
+	pub fn at(&self, timestamp: Timestamp) -> Option<&PreTrustSet> {
+		self.versions.range(..=timestamp).next_back().map(|(_, set)| set)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_load_and_normalize_file() {
+		let dir = std::env::temp_dir().join(format!(
+			"core-compute-{}-load-pre-trust",
+			std::process::id()
+		));
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("pre-trust.csv");
+		fs::write(&path, "# did;weight\nalice;3\n\nbob; 1\ncarol;-1\n").unwrap();
+
+		let entries = load_file(&path).unwrap();
+		assert_eq!(entries.len(), 3);
+		let set = normalize(entries);
+		assert_eq!(set["alice"], 0.75);
+		assert_eq!(set["bob"], 0.25);
+		assert!(!set.contains_key("carol"));
+
+		fs::write(&path, "alice\n").unwrap();
+		assert!(load_file(&path).is_err());
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn should_pick_version_valid_at_timestamp() {
+		let mut pre_trust = PreTrust::default();
+		pre_trust.insert(Timestamp::new(10), vec![("alice".to_string(), 1.)]);
+		pre_trust.insert(Timestamp::new(20), vec![("bob".to_string(), 1.)]);
+
+		assert_eq!(pre_trust.at(Timestamp::new(5)), None);
+		assert!(pre_trust.at(Timestamp::new(10)).unwrap().contains_key("alice"));
+		assert!(pre_trust.at(Timestamp::new(19)).unwrap().contains_key("alice"));
+		assert!(pre_trust.at(Timestamp::new(25)).unwrap().contains_key("bob"));
+	}
+}
@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::{TimeZone, Utc};
+use secp256k1::{Secp256k1, SecretKey};
+
+use mm_spd_vc::error::VcError;
+use mm_spd_vc::proof::{did_from_ecdsa_key, Signable};
+use mm_spd_vc::{
+	Manifest, ManifestProof, OneOrMore, TrustScore, TrustScoreCredential,
+	TrustScoreCredentialProof, TrustScoreCredentialSubject,
+};
+use snap_score_computer::badge::SnapBadge;
+use snap_score_computer::score::SnapScore;
+
+use crate::error::CoreError;
+
+const VC_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+pub const PEER_SCORE_TYPE: &str = "EigenTrust";
+pub const SNAP_SCORE_TYPE: &str = "IssuerTrustWeightedAverage";
+const PEER_SCORES_FILE: &str = "peer_scores.jsonl";
+const SNAP_SCORES_FILE: &str = "snap_scores.jsonl";
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Scores of a peer, as `T+` and `T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerResult {
+	pub did: String,
+	pub positive: f64,
+	pub adjusted: f64,
+}
+
+/// Signs the compute results as `TrustScoreCredential`s and writes them,
+/// along with the epoch `Manifest`, under `<output_dir>/<epoch>/<scope>/`.
+#[derive(Debug, Clone)]
+pub struct Publisher {
+	secret_key: SecretKey,
+	issuer: String,
+	output_dir: PathBuf,
+}
+
+impl Publisher {
+	pub fn new(secret_key: SecretKey, output_dir: PathBuf) -> Self {
+		let issuer = did_from_ecdsa_key(&secret_key.public_key(&Secp256k1::signing_only()));
+		Self { secret_key, issuer, output_dir }
+	}
+
+	pub fn issuer(&self) -> &str {
+		&self.issuer
+	}
+
+	fn credential(
+		&self, epoch: u64, subject: &str, score_type: &str, trust_score: TrustScore,
+	) -> Result<TrustScoreCredential, CoreError> {
+		let mut credential = TrustScoreCredential {
+			context: vec![VC_CONTEXT.to_string()],
+			id: format!(
+				"urn:trust-score:{}:{}:{}",
+				trust_score.scope, epoch, subject
+			),
+			type_: OneOrMore::More(vec![
+				"VerifiableCredential".to_string(),
+				"TrustScoreCredential".to_string(),
+			]),
+			issuer: self.issuer.clone(),
+			issuance_date: format_date(epoch)?,
+			credential_subject: TrustScoreCredentialSubject {
+				id: subject.to_string(),
+				trust_score_type: score_type.to_string(),
+				trust_score,
+			},
+			proof: TrustScoreCredentialProof::default(),
+		};
+		credential.sign(&self.secret_key).map_err(CoreError::VcError)?;
+		Ok(credential)
+	}
+
+	/// Peer credentials, ranked by their distrust-adjusted score.
+	pub fn peer_credentials(
+		&self, epoch: u64, scope: &str, peers: &[PeerResult],
+	) -> Result<Vec<TrustScoreCredential>, CoreError> {
+		peer_scores(scope, peers)
+			.into_iter()
+			.map(|(did, trust_score)| self.credential(epoch, &did, PEER_SCORE_TYPE, trust_score))
+			.collect()
+	}
+
+	/// Snap credentials, with the badge carried as the result.
+	pub fn snap_credentials(
+		&self, epoch: u64, scope: &str, snaps: &BTreeMap<String, (SnapScore, SnapBadge)>,
+	) -> Result<Vec<TrustScoreCredential>, CoreError> {
+		snap_scores(scope, snaps)
+			.into_iter()
+			.map(|(snap, trust_score)| self.credential(epoch, &snap, SNAP_SCORE_TYPE, trust_score))
+			.collect()
+	}
+
+	/// Write the JCS-canonicalized credentials, one per line, followed by the signed manifest.
+	pub fn publish(
+		&self, epoch: u64, scope: &str, peers: &[TrustScoreCredential],
+		snaps: &[TrustScoreCredential], trust_threshold: f64,
+	) -> Result<Manifest, CoreError> {
+		let dir = self.output_dir.join(epoch.to_string()).join(scope);
+		fs::create_dir_all(&dir).map_err(CoreError::IoError)?;
+
+		let files = [(PEER_SCORES_FILE, peers), (SNAP_SCORES_FILE, snaps)];
+		for (file, credentials) in files {
+			let mut lines = String::new();
+			for credential in credentials {
+				lines += &serde_jcs::to_string(credential)
+					.map_err(|e| CoreError::VcError(VcError::SerdeError(e)))?;
+				lines += "\n";
+			}
+			fs::write(dir.join(file), lines).map_err(CoreError::IoError)?;
+		}
+
+		let date = format_date(epoch)?;
+		let mut manifest = Manifest {
+			issuer: self.issuer.clone(),
+			issuance_date: Utc::now().to_rfc3339(),
+			effective_date: date,
+			epoch: epoch.to_string(),
+			scope: scope.to_string(),
+			locations: vec![PEER_SCORES_FILE.to_string(), SNAP_SCORES_FILE.to_string()],
+			trust_threshold,
+			proof: ManifestProof::default(),
+		};
+		manifest.sign(&self.secret_key).map_err(CoreError::VcError)?;
+
+		let bytes =
+			serde_jcs::to_vec(&manifest).map_err(|e| CoreError::VcError(VcError::SerdeError(e)))?;
+		fs::write(dir.join(MANIFEST_FILE), bytes).map_err(CoreError::IoError)?;
+
+		Ok(manifest)
+	}
+}
+
+/// Peer scores, ranked by the distrust-adjusted score.
+pub fn peer_scores(scope: &str, peers: &[PeerResult]) -> Vec<(String, TrustScore)> {
+	let mut ranked: Vec<&PeerResult> = peers.iter().collect();
+	ranked.sort_by(|a, b| b.adjusted.total_cmp(&a.adjusted));
+
+	ranked
+		.into_iter()
+		.enumerate()
+		.map(|(i, peer)| {
+			let trust_score = TrustScore {
+				value: peer.adjusted,
+				value_before_discount: Some(peer.positive),
+				confidence: None,
+				result: None,
+				accuracy: None,
+				rank: Some(i as u64 + 1),
+				scope: scope.to_string(),
+			};
+			(peer.did.clone(), trust_score)
+		})
+		.collect()
+}
+
+pub fn snap_scores(
+	scope: &str, snaps: &BTreeMap<String, (SnapScore, SnapBadge)>,
+) -> Vec<(String, TrustScore)> {
+	snaps
+		.iter()
+		.map(|(snap, (score, badge))| {
+			let trust_score = TrustScore {
+				value: score.value,
+				value_before_discount: None,
+				confidence: Some(score.confidence),
+				result: badge_result(badge),
+				accuracy: None,
+				rank: None,
+				scope: scope.to_string(),
+			};
+			(snap.clone(), trust_score)
+		})
+		.collect()
+}
+
+/// 1 for Endorsed, 0 for In Review, -1 for Reported, and none for Insufficient Reviews.
+pub fn badge_result(badge: &SnapBadge) -> Option<i32> {
+	match badge {
+		SnapBadge::Endorsed => Some(1),
+		SnapBadge::InReview => Some(0),
+		SnapBadge::Reported => Some(-1),
+		SnapBadge::InsufficientReviews => None,
+	}
+}
+
+/// RFC 3339 date of an epoch given in seconds since the unix epoch.
+fn format_date(epoch: u64) -> Result<String, CoreError> {
+	let secs = i64::try_from(epoch).map_err(|_| CoreError::ParseError)?;
+	let date = Utc.timestamp_opt(secs, 0).single().ok_or(CoreError::ParseError)?;
+	Ok(date.to_rfc3339())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_publish_signed_credentials_and_manifest() {
+		let output_dir =
+			std::env::temp_dir().join(format!("core-compute-{}-publish", std::process::id()));
+		let publisher =
+			Publisher::new(SecretKey::from_slice(&[7; 32]).unwrap(), output_dir.clone());
+
+		let peers = vec![
+			PeerResult { did: "alice".to_string(), positive: 0.25, adjusted: 0.125 },
+			PeerResult { did: "bob".to_string(), positive: 0.75, adjusted: 0.75 },
+		];
+		let snaps = BTreeMap::from([(
+			"snap://a".to_string(),
+			(
+				SnapScore { value: 1., confidence: 0.75 },
+				SnapBadge::Endorsed,
+			),
+		)]);
+
+		let peers = publisher.peer_credentials(1700000000, "SoftwareSecurity", &peers).unwrap();
+		assert_eq!(peers[0].credential_subject.id, "bob");
+		assert_eq!(peers[0].credential_subject.trust_score.rank, Some(1));
+		let snaps = publisher.snap_credentials(1700000000, "SoftwareSecurity", &snaps).unwrap();
+		assert_eq!(snaps[0].credential_subject.trust_score.result, Some(1));
+
+		let manifest =
+			publisher.publish(1700000000, "SoftwareSecurity", &peers, &snaps, 0.25).unwrap();
+		manifest.verify().unwrap();
+
+		let lines = fs::read_to_string(
+			output_dir.join("1700000000/SoftwareSecurity").join(PEER_SCORES_FILE),
+		)
+		.unwrap();
+		for line in lines.lines() {
+			let credential: TrustScoreCredential = serde_json::from_str(line).unwrap();
+			credential.verify().unwrap();
+		}
+		fs::remove_dir_all(output_dir).unwrap();
+	}
+}
@@ -0,0 +1,507 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use tokio::sync::mpsc::channel;
+use tokio::sync::Mutex as AsyncMutex;
+use tokio::task;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use proto_buf::eigentrust::compute_server::Compute;
+use proto_buf::eigentrust::get_trust_matrix_response::Part as MatrixPart;
+use proto_buf::eigentrust::get_trust_vector_response::Part as VectorPart;
+use proto_buf::eigentrust::{
+	BasicComputeRequest, BasicComputeResponse, ComputeParams as ComputeParamsPb, Contribution,
+	CreateComputeJobRequest, CreateComputeJobResponse, CreateTrustMatrixRequest,
+	CreateTrustMatrixResponse, CreateTrustVectorRequest, CreateTrustVectorResponse,
+	DeleteComputeJobRequest, DeleteComputeJobResponse, DeleteTrustMatrixRequest,
+	DeleteTrustMatrixResponse, DeleteTrustVectorRequest, DeleteTrustVectorResponse, ExplainRequest,
+	ExplainResponse, FlushTrustMatrixRequest, FlushTrustMatrixResponse, FlushTrustVectorRequest,
+	FlushTrustVectorResponse, GetTrustMatrixRequest, GetTrustMatrixResponse, GetTrustVectorRequest,
+	GetTrustVectorResponse, TrustMatrixEntry, TrustMatrixHeader, TrustVectorEntry,
+	TrustVectorHeader, UpdateTrustMatrixRequest, UpdateTrustMatrixResponse,
+	UpdateTrustVectorRequest, UpdateTrustVectorResponse,
+};
+
+use crate::eigentrust::{ComputeParams, ComputeResult};
+use crate::error::CoreError;
+use crate::store::{ComputeInput, ComputeJob, Store};
+use crate::timestamp::Timestamp;
+
+/// Compute request parameters, with the defaults filled in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputeRequest {
+	pub local_trust_id: String,
+	pub pre_trust_id: String,
+	pub global_trust_id: String,
+	pub params: ComputeParams,
+}
+
+impl TryFrom<ComputeParamsPb> for ComputeRequest {
+	type Error = Status;
+
+	fn try_from(value: ComputeParamsPb) -> Result<Self, Self::Error> {
+		if !value.destinations.is_empty() {
+			return Err(Status::unimplemented(
+				"Pushing to destinations is not supported.",
+			));
+		}
+
+		let defaults = ComputeParams::default();
+		let params = ComputeParams {
+			alpha: value.alpha.unwrap_or(defaults.alpha),
+			epsilon: value.epsilon.unwrap_or(defaults.epsilon),
+			max_iterations: value.max_iterations,
+			flat_tail: value.flat_tail,
+			num_leaders: value.num_leaders,
+		};
+		params.validate()?;
+
+		Ok(Self {
+			local_trust_id: value.local_trust_id,
+			pre_trust_id: value.pre_trust_id,
+			global_trust_id: value.global_trust_id,
+			params,
+		})
+	}
+}
+
+/// go-eigentrust compatible `Compute` service, backed by an in-process store.
+/// Computes run on the blocking pool, the store is only locked to copy their inputs
+/// out and to store their results back.
+#[derive(Debug, Clone, Default)]
+pub struct ComputeService {
+	store: Arc<Mutex<Store>>,
+	// serializes the requests that compute, so that their results are stored in order
+	computes: Arc<AsyncMutex<()>>,
+}
+
+impl ComputeService {
+	pub fn new(store: Arc<Mutex<Store>>) -> Self {
+		Self { store, computes: Arc::default() }
+	}
+
+	fn lock(&self) -> Result<MutexGuard<Store>, Status> {
+		self.store.lock().map_err(|_| Status::internal("Internal error: poisoned store lock"))
+	}
+}
+
+/// Run the computes on the blocking pool, so that they hold up neither the store nor the runtime.
+async fn run_blocking(
+	inputs: Vec<ComputeInput>,
+) -> Result<Vec<(ComputeInput, ComputeResult)>, Status> {
+	let results = task::spawn_blocking(move || {
+		inputs
+			.into_iter()
+			.map(|input| {
+				let res = input.run()?;
+				Ok((input, res))
+			})
+			.collect::<Result<Vec<_>, CoreError>>()
+	})
+	.await
+	.map_err(|e| Status::internal(format!("Internal error: {}", e)))?;
+	Ok(results?)
+}
+
+fn header_id(id: Option<String>) -> Result<String, Status> {
+	id.ok_or_else(|| Status::invalid_argument("Missing `header.id`."))
+}
+
+#[tonic::async_trait]
+impl Compute for ComputeService {
+	type GetTrustMatrixStream = ReceiverStream<Result<GetTrustMatrixResponse, Status>>;
+	type GetTrustVectorStream = ReceiverStream<Result<GetTrustVectorResponse, Status>>;
+
+	async fn create_trust_matrix(
+		&self, _request: Request<CreateTrustMatrixRequest>,
+	) -> Result<Response<CreateTrustMatrixResponse>, Status> {
+		let id = self.lock()?.create_matrix();
+		Ok(Response::new(CreateTrustMatrixResponse { id }))
+	}
+
+	async fn get_trust_matrix(
+		&self, request: Request<GetTrustMatrixRequest>,
+	) -> Result<Response<Self::GetTrustMatrixStream>, Status> {
+		let id = request.into_inner().id;
+		let parts = {
+			let store = self.lock()?;
+			let matrix = store.get_matrix(&id)?;
+
+			let header = TrustMatrixHeader {
+				id: Some(id.clone()),
+				timestamp_qwords: matrix.timestamp().to_qwords(),
+			};
+			let mut parts = vec![MatrixPart::Header(header)];
+			parts.extend(matrix.entries().map(|(truster, trustee, value)| {
+				MatrixPart::Entry(TrustMatrixEntry {
+					truster: truster.clone(),
+					trustee: trustee.clone(),
+					value,
+				})
+			}));
+			parts
+		};
+
+		let (tx, rx) = channel(4);
+		tokio::spawn(async move {
+			for part in parts {
+				let res = GetTrustMatrixResponse { part: Some(part) };
+				if tx.send(Ok(res)).await.is_err() {
+					break;
+				}
+			}
+		});
+
+		Ok(Response::new(ReceiverStream::new(rx)))
+	}
+
+	async fn update_trust_matrix(
+		&self, request: Request<UpdateTrustMatrixRequest>,
+	) -> Result<Response<UpdateTrustMatrixResponse>, Status> {
+		let inner = request.into_inner();
+		let header = inner.header.unwrap_or_default();
+		let id = header_id(header.id)?;
+		let timestamp = Timestamp::from_qwords(&header.timestamp_qwords)?;
+		let entries = inner.entries.into_iter().map(|x| (x.truster, x.trustee, x.value)).collect();
+
+		// The jobs triggered by the update are computed first, leaving nothing for it to trigger
+		let _computes = self.computes.lock().await;
+		let inputs = {
+			let store = self.lock()?;
+			store.get_matrix(&id)?.check_timestamp(timestamp)?;
+			store.triggered_jobs(timestamp, |job| job.local_trust_id == id)?
+		};
+		let results = run_blocking(inputs).await?;
+
+		let mut store = self.lock()?;
+		for (input, res) in results {
+			store.store_result(input, &res)?;
+		}
+		store.update_matrix(&id, timestamp, entries)?;
+		Ok(Response::new(UpdateTrustMatrixResponse {}))
+	}
+
+	async fn flush_trust_matrix(
+		&self, request: Request<FlushTrustMatrixRequest>,
+	) -> Result<Response<FlushTrustMatrixResponse>, Status> {
+		let id = request.into_inner().id;
+		self.lock()?.get_matrix_mut(&id)?.flush();
+		Ok(Response::new(FlushTrustMatrixResponse {}))
+	}
+
+	async fn delete_trust_matrix(
+		&self, request: Request<DeleteTrustMatrixRequest>,
+	) -> Result<Response<DeleteTrustMatrixResponse>, Status> {
+		let id = request.into_inner().id;
+		self.lock()?.delete_matrix(&id)?;
+		Ok(Response::new(DeleteTrustMatrixResponse {}))
+	}
+
+	async fn create_trust_vector(
+		&self, _request: Request<CreateTrustVectorRequest>,
+	) -> Result<Response<CreateTrustVectorResponse>, Status> {
+		let id = self.lock()?.create_vector();
+		Ok(Response::new(CreateTrustVectorResponse { id }))
+	}
+
+	async fn get_trust_vector(
+		&self, request: Request<GetTrustVectorRequest>,
+	) -> Result<Response<Self::GetTrustVectorStream>, Status> {
+		let id = request.into_inner().id;
+		let parts = {
+			let store = self.lock()?;
+			let vector = store.get_vector(&id)?;
+
+			let header = TrustVectorHeader {
+				id: Some(id.clone()),
+				timestamp_qwords: vector.timestamp().to_qwords(),
+			};
+			let mut parts = vec![VectorPart::Header(header)];
+			parts.extend(vector.entries().map(|(trustee, value)| {
+				VectorPart::Entry(TrustVectorEntry { trustee: trustee.clone(), value })
+			}));
+			parts
+		};
+
+		let (tx, rx) = channel(4);
+		tokio::spawn(async move {
+			for part in parts {
+				let res = GetTrustVectorResponse { part: Some(part) };
+				if tx.send(Ok(res)).await.is_err() {
+					break;
+				}
+			}
+		});
+
+		Ok(Response::new(ReceiverStream::new(rx)))
+	}
+
+	async fn update_trust_vector(
+		&self, request: Request<UpdateTrustVectorRequest>,
+	) -> Result<Response<UpdateTrustVectorResponse>, Status> {
+		let inner = request.into_inner();
+		let header = inner.header.unwrap_or_default();
+		let id = header_id(header.id)?;
+		let timestamp = Timestamp::from_qwords(&header.timestamp_qwords)?;
+		let entries = inner.entries.into_iter().map(|x| (x.trustee, x.value)).collect();
+
+		// The jobs triggered by the update are computed first, leaving nothing for it to trigger
+		let _computes = self.computes.lock().await;
+		let inputs = {
+			let store = self.lock()?;
+			store.get_vector(&id)?.check_timestamp(timestamp)?;
+			store.triggered_jobs(timestamp, |job| job.pre_trust_id == id)?
+		};
+		let results = run_blocking(inputs).await?;
+
+		let mut store = self.lock()?;
+		for (input, res) in results {
+			store.store_result(input, &res)?;
+		}
+		store.update_vector(&id, timestamp, entries)?;
+		Ok(Response::new(UpdateTrustVectorResponse {}))
+	}
+
+	async fn flush_trust_vector(
+		&self, request: Request<FlushTrustVectorRequest>,
+	) -> Result<Response<FlushTrustVectorResponse>, Status> {
+		let id = request.into_inner().id;
+		self.lock()?.get_vector_mut(&id)?.flush();
+		Ok(Response::new(FlushTrustVectorResponse {}))
+	}
+
+	async fn delete_trust_vector(
+		&self, request: Request<DeleteTrustVectorRequest>,
+	) -> Result<Response<DeleteTrustVectorResponse>, Status> {
+		let id = request.into_inner().id;
+		self.lock()?.delete_vector(&id)?;
+		Ok(Response::new(DeleteTrustVectorResponse {}))
+	}
+
+	async fn basic_compute(
+		&self, request: Request<BasicComputeRequest>,
+	) -> Result<Response<BasicComputeResponse>, Status> {
+		let params = request.into_inner().params.unwrap_or_default();
+		let req = ComputeRequest::try_from(params)?;
+
+		let _computes = self.computes.lock().await;
+		let input = {
+			let store = self.lock()?;
+			// The result reflects all the inputs, so it bears the latest of their timestamps
+			let timestamp = [
+				store.get_matrix(&req.local_trust_id)?.timestamp(),
+				store.get_vector(&req.pre_trust_id)?.timestamp(),
+				store.get_vector(&req.global_trust_id)?.timestamp(),
+			]
+			.into_iter()
+			.max()
+			.unwrap_or_default();
+			store.compute_input(
+				&req.local_trust_id, &req.pre_trust_id, &req.global_trust_id, &req.params,
+				timestamp,
+			)?
+		};
+		let (input, res) = run_blocking(vec![input]).await?.remove(0);
+		self.lock()?.store_result(input, &res)?;
+
+		Ok(Response::new(BasicComputeResponse {
+			iterations: res.iterations,
+			flat_tail: res.flat_tail,
+		}))
+	}
+
+	async fn create_compute_job(
+		&self, request: Request<CreateComputeJobRequest>,
+	) -> Result<Response<CreateComputeJobResponse>, Status> {
+		let spec = request.into_inner().spec.unwrap_or_default();
+		let req = ComputeRequest::try_from(spec.params.unwrap_or_default())?;
+		let job = ComputeJob {
+			local_trust_id: req.local_trust_id,
+			pre_trust_id: req.pre_trust_id,
+			global_trust_id: req.global_trust_id,
+			params: req.params,
+			period: Timestamp::from_qwords(&spec.period_qwords)?,
+		};
+
+		let id = self.lock()?.create_job(job)?;
+		Ok(Response::new(CreateComputeJobResponse { id }))
+	}
+
+	async fn delete_compute_job(
+		&self, request: Request<DeleteComputeJobRequest>,
+	) -> Result<Response<DeleteComputeJobResponse>, Status> {
+		let id = request.into_inner().id;
+		self.lock()?.delete_job(&id)?;
+		Ok(Response::new(DeleteComputeJobResponse {}))
+	}
+
+	async fn explain(
+		&self, request: Request<ExplainRequest>,
+	) -> Result<Response<ExplainResponse>, Status> {
+		let ExplainRequest { domain, did } = request.into_inner();
+		let store = self.lock()?;
+		let explanation = store.get_explanation(domain, &did)?;
+
+		let contributions = explanation
+			.contributions
+			.iter()
+			.map(|x| Contribution {
+				did: x.peer.clone(),
+				trust: x.trust,
+				opinion: x.opinion,
+				share: x.share,
+			})
+			.collect();
+		Ok(Response::new(ExplainResponse {
+			contributions,
+			auditors: explanation.auditors.clone(),
+		}))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::collections::BTreeMap;
+
+	use proto_buf::eigentrust::ComputeJobSpec;
+	use snap_score_computer::explain::{Contribution as ExplanationContribution, Explanation};
+	use tokio_stream::StreamExt;
+
+	use super::*;
+
+	#[tokio::test]
+	async fn should_basic_compute() {
+		let service = ComputeService::default();
+		let lt_id = service
+			.create_trust_matrix(Request::new(CreateTrustMatrixRequest {}))
+			.await
+			.unwrap()
+			.into_inner()
+			.id;
+		let pt_id = service
+			.create_trust_vector(Request::new(CreateTrustVectorRequest {}))
+			.await
+			.unwrap()
+			.into_inner()
+			.id;
+		let gt_id = service
+			.create_trust_vector(Request::new(CreateTrustVectorRequest {}))
+			.await
+			.unwrap()
+			.into_inner()
+			.id;
+
+		let entry = |truster: &str, trustee: &str| TrustMatrixEntry {
+			truster: truster.to_string(),
+			trustee: trustee.to_string(),
+			value: 1.,
+		};
+		let lt_update = UpdateTrustMatrixRequest {
+			header: Some(TrustMatrixHeader { id: Some(lt_id.clone()), timestamp_qwords: vec![7] }),
+			entries: vec![entry("alice", "bob"), entry("bob", "alice")],
+		};
+		service.update_trust_matrix(Request::new(lt_update)).await.unwrap();
+
+		let pt_update = UpdateTrustVectorRequest {
+			header: Some(TrustVectorHeader { id: Some(pt_id.clone()), timestamp_qwords: vec![3] }),
+			entries: vec![TrustVectorEntry { trustee: "alice".to_string(), value: 1. }],
+		};
+		service.update_trust_vector(Request::new(pt_update)).await.unwrap();
+
+		let params = ComputeParamsPb {
+			local_trust_id: lt_id,
+			pre_trust_id: pt_id,
+			global_trust_id: gt_id.clone(),
+			..ComputeParamsPb::default()
+		};
+		let req = BasicComputeRequest { params: Some(params) };
+		let res = service.basic_compute(Request::new(req)).await.unwrap().into_inner();
+		assert!(res.iterations > 0);
+
+		let req = GetTrustVectorRequest { id: gt_id };
+		let mut stream = service.get_trust_vector(Request::new(req)).await.unwrap().into_inner();
+		let mut parts = Vec::new();
+		while let Some(res) = stream.next().await {
+			parts.push(res.unwrap().part.unwrap());
+		}
+
+		assert_eq!(parts.len(), 3);
+		match &parts[0] {
+			VectorPart::Header(header) => assert_eq!(header.timestamp_qwords, vec![7]),
+			_ => panic!("Header must come first"),
+		}
+	}
+
+	#[tokio::test]
+	async fn should_create_and_delete_compute_job() {
+		let service = ComputeService::default();
+		let (lt_id, pt_id, gt_id) = {
+			let mut store = service.lock().unwrap();
+			(
+				store.create_matrix(),
+				store.create_vector(),
+				store.create_vector(),
+			)
+		};
+		let params = ComputeParamsPb {
+			local_trust_id: lt_id.clone(),
+			pre_trust_id: pt_id,
+			global_trust_id: gt_id,
+			..ComputeParamsPb::default()
+		};
+
+		let spec = ComputeJobSpec { params: Some(params.clone()), period_qwords: vec![] };
+		let req = CreateComputeJobRequest { spec: Some(spec) };
+		let err = service.create_compute_job(Request::new(req)).await.unwrap_err();
+		assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+		let spec = ComputeJobSpec { params: Some(params), period_qwords: vec![1000] };
+		let req = CreateComputeJobRequest { spec: Some(spec) };
+		let id = service.create_compute_job(Request::new(req)).await.unwrap().into_inner().id;
+
+		let req = DeleteTrustMatrixRequest { id: lt_id.clone() };
+		assert!(service.delete_trust_matrix(Request::new(req)).await.is_err());
+
+		let req = DeleteComputeJobRequest { id };
+		service.delete_compute_job(Request::new(req)).await.unwrap();
+		let req = DeleteTrustMatrixRequest { id: lt_id };
+		service.delete_trust_matrix(Request::new(req)).await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn should_explain_by_did() {
+		let service = ComputeService::default();
+		let explanation = Explanation {
+			contributions: vec![ExplanationContribution {
+				peer: "alice".to_string(),
+				trust: 0.5,
+				opinion: -1.,
+				share: 1.,
+			}],
+			auditors: vec!["alice".to_string()],
+		};
+		service
+			.lock()
+			.unwrap()
+			.set_explanations(2, BTreeMap::from([("bob".to_string(), explanation)]));
+
+		let req = ExplainRequest { domain: 2, did: "bob".to_string() };
+		let res = service.explain(Request::new(req)).await.unwrap().into_inner();
+		assert_eq!(res.contributions.len(), 1);
+		assert_eq!(res.contributions[0].did, "alice");
+		assert_eq!(res.contributions[0].opinion, -1.);
+		assert_eq!(res.auditors, vec!["alice".to_string()]);
+
+		let req = ExplainRequest { domain: 1, did: "bob".to_string() };
+		let err = service.explain(Request::new(req)).await.unwrap_err();
+		assert_eq!(err.code(), tonic::Code::NotFound);
+	}
+
+	#[tokio::test]
+	async fn should_reject_unknown_ids() {
+		let service = ComputeService::default();
+		let req = FlushTrustMatrixRequest { id: "unknown".to_string() };
+		let err = service.flush_trust_matrix(Request::new(req)).await.unwrap_err();
+		assert_eq!(err.code(), tonic::Code::NotFound);
+	}
+}
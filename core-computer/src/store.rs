@@ -0,0 +1,559 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use snap_score_computer::explain::Explanation;
+
+use crate::eigentrust::{eigentrust, ComputeParams, ComputeResult};
+use crate::error::CoreError;
+use crate::matrix::LocalTrust;
+use crate::pretrust::PreTrust;
+use crate::timestamp::Timestamp;
+
+/// Trust matrix keyed by truster and trustee DIDs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrustMatrix {
+	timestamp: Timestamp,
+	entries: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+impl TrustMatrix {
+	pub fn timestamp(&self) -> Timestamp {
+		self.timestamp
+	}
+
+	/// Updates must not go back in time.
+	pub fn check_timestamp(&self, timestamp: Timestamp) -> Result<(), CoreError> {
+		if timestamp < self.timestamp {
+			return Err(CoreError::InvalidParamsError(format!(
+				"update timestamp {} is older than {}",
+				timestamp.value(),
+				self.timestamp.value()
+			)));
+		}
+		Ok(())
+	}
+
+	/// Apply a batch of entries, all bearing the same timestamp.
+	/// Zero values remove the entry.
+	pub fn update(
+		&mut self, timestamp: Timestamp, entries: Vec<(String, String, f64)>,
+	) -> Result<(), CoreError> {
+		self.check_timestamp(timestamp)?;
+		for (truster, trustee, value) in entries {
+			if value == 0. {
+				if let Some(row) = self.entries.get_mut(&truster) {
+					row.remove(&trustee);
+					if row.is_empty() {
+						self.entries.remove(&truster);
+					}
+				}
+				continue;
+			}
+			self.entries.entry(truster).or_default().insert(trustee, value);
+		}
+		self.timestamp = timestamp;
+		Ok(())
+	}
+
+	pub fn flush(&mut self) {
+		self.entries.clear();
+	}
+
+	pub fn entries(&self) -> impl Iterator<Item = (&String, &String, f64)> + '_ {
+		self.entries.iter().flat_map(|(x, row)| row.iter().map(move |(y, value)| (x, y, *value)))
+	}
+}
+
+/// Trust vector keyed by trustee DIDs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrustVector {
+	timestamp: Timestamp,
+	entries: BTreeMap<String, f64>,
+}
+
+impl TrustVector {
+	pub fn timestamp(&self) -> Timestamp {
+		self.timestamp
+	}
+
+	/// Updates must not go back in time.
+	pub fn check_timestamp(&self, timestamp: Timestamp) -> Result<(), CoreError> {
+		if timestamp < self.timestamp {
+			return Err(CoreError::InvalidParamsError(format!(
+				"update timestamp {} is older than {}",
+				timestamp.value(),
+				self.timestamp.value()
+			)));
+		}
+		Ok(())
+	}
+
+	/// Apply a batch of entries, all bearing the same timestamp.
+	/// Zero values remove the entry.
+	pub fn update(
+		&mut self, timestamp: Timestamp, entries: Vec<(String, f64)>,
+	) -> Result<(), CoreError> {
+		self.check_timestamp(timestamp)?;
+		for (trustee, value) in entries {
+			if value == 0. {
+				self.entries.remove(&trustee);
+				continue;
+			}
+			self.entries.insert(trustee, value);
+		}
+		self.timestamp = timestamp;
+		Ok(())
+	}
+
+	pub fn flush(&mut self) {
+		self.entries.clear();
+	}
+
+	pub fn entries(&self) -> impl Iterator<Item = (&String, f64)> + '_ {
+		self.entries.iter().map(|(x, value)| (x, *value))
+	}
+
+	pub fn get(&self, trustee: &str) -> f64 {
+		self.entries.get(trustee).copied().unwrap_or(0.)
+	}
+}
+
+/// Periodic compute job, see `ComputeJobSpec` in `eigentrust.proto`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputeJob {
+	pub local_trust_id: String,
+	pub pre_trust_id: String,
+	pub global_trust_id: String,
+	pub params: ComputeParams,
+	pub period: Timestamp,
+}
+
+impl ComputeJob {
+	fn uses(&self, id: &str) -> bool {
+		self.local_trust_id == id || self.pre_trust_id == id || self.global_trust_id == id
+	}
+}
+
+/// In-process store of the trust matrices, vectors and compute jobs served by the `Compute` service.
+#[derive(Debug, Default)]
+pub struct Store {
+	matrices: HashMap<String, TrustMatrix>,
+	vectors: HashMap<String, TrustVector>,
+	jobs: HashMap<String, ComputeJob>,
+	/// Version history of the vectors holding the pre-trust of a domain.
+	pre_trust: HashMap<String, PreTrust>,
+	/// Explanations of the latest scores, by domain and DID.
+	explanations: HashMap<u32, BTreeMap<String, Explanation>>,
+	next_id: u64,
+}
+
+/// ID of the vector holding the pre-trust of the given domain.
+/// Its updates must be timestamped in seconds since the unix epoch,
+/// as the versions are looked up by the epoch of the compute.
+pub fn pre_trust_id(domain: u32) -> String {
+	format!("pre-trust-{}", domain)
+}
+
+impl Store {
+	fn generate_id(&mut self) -> String {
+		self.next_id += 1;
+		format!("{:016x}", self.next_id)
+	}
+
+	pub fn create_matrix(&mut self) -> String {
+		let id = self.generate_id();
+		self.matrices.insert(id.clone(), TrustMatrix::default());
+		id
+	}
+
+	pub fn get_matrix(&self, id: &str) -> Result<&TrustMatrix, CoreError> {
+		self.matrices.get(id).ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	pub fn get_matrix_mut(&mut self, id: &str) -> Result<&mut TrustMatrix, CoreError> {
+		self.matrices.get_mut(id).ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	/// Update a matrix, re-computing the jobs that take it as local trust beforehand.
+	pub fn update_matrix(
+		&mut self, id: &str, timestamp: Timestamp, entries: Vec<(String, String, f64)>,
+	) -> Result<(), CoreError> {
+		self.get_matrix(id)?.check_timestamp(timestamp)?;
+		self.trigger_jobs(timestamp, |job| job.local_trust_id == id)?;
+		self.get_matrix_mut(id)?.update(timestamp, entries)
+	}
+
+	pub fn delete_matrix(&mut self, id: &str) -> Result<(), CoreError> {
+		self.check_unused(id)?;
+		self.matrices
+			.remove(id)
+			.map(|_| ())
+			.ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	pub fn create_vector(&mut self) -> String {
+		let id = self.generate_id();
+		self.vectors.insert(id.clone(), TrustVector::default());
+		id
+	}
+
+	pub fn get_vector(&self, id: &str) -> Result<&TrustVector, CoreError> {
+		self.vectors.get(id).ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	pub fn get_vector_mut(&mut self, id: &str) -> Result<&mut TrustVector, CoreError> {
+		self.vectors.get_mut(id).ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	/// Update a vector, re-computing the jobs that take it as pre-trust beforehand.
+	pub fn update_vector(
+		&mut self, id: &str, timestamp: Timestamp, entries: Vec<(String, f64)>,
+	) -> Result<(), CoreError> {
+		self.get_vector(id)?.check_timestamp(timestamp)?;
+		self.trigger_jobs(timestamp, |job| job.pre_trust_id == id)?;
+		let vector =
+			self.vectors.get_mut(id).ok_or_else(|| CoreError::NotFoundError(id.to_string()))?;
+		vector.update(timestamp, entries)?;
+
+		if let Some(pre_trust) = self.pre_trust.get_mut(id) {
+			let entries = vector.entries().map(|(x, value)| (x.clone(), value)).collect();
+			pre_trust.insert(timestamp, entries);
+		}
+		Ok(())
+	}
+
+	/// Create the vector holding the pre-trust of the given domain, unless it already exists.
+	/// Every update of its contents is recorded as a new version of the pre-trust set,
+	/// valid from the update timestamp on.
+	/// Timestamps are in seconds since the unix epoch: a version stamped in milliseconds
+	/// would only become valid in a far future.
+	pub fn create_pre_trust(&mut self, domain: u32) -> String {
+		let id = pre_trust_id(domain);
+		self.vectors.entry(id.clone()).or_default();
+		self.pre_trust.entry(id.clone()).or_default();
+		id
+	}
+
+	pub fn get_pre_trust(&self, domain: u32) -> Result<&PreTrust, CoreError> {
+		let id = pre_trust_id(domain);
+		self.pre_trust.get(&id).ok_or(CoreError::NotFoundError(id))
+	}
+
+	/// Replace the explanations of the scores of the given domain with the latest ones.
+	pub fn set_explanations(&mut self, domain: u32, explanations: BTreeMap<String, Explanation>) {
+		self.explanations.insert(domain, explanations);
+	}
+
+	pub fn get_explanation(&self, domain: u32, did: &str) -> Result<&Explanation, CoreError> {
+		self.explanations
+			.get(&domain)
+			.and_then(|x| x.get(did))
+			.ok_or_else(|| CoreError::NotFoundError(did.to_string()))
+	}
+
+	pub fn delete_vector(&mut self, id: &str) -> Result<(), CoreError> {
+		self.check_unused(id)?;
+		self.pre_trust.remove(id);
+		self.vectors
+			.remove(id)
+			.map(|_| ())
+			.ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	pub fn create_job(&mut self, job: ComputeJob) -> Result<String, CoreError> {
+		if job.period == Timestamp::default() {
+			return Err(CoreError::InvalidParamsError(
+				"period must be positive".to_string(),
+			));
+		}
+		self.get_matrix(&job.local_trust_id)?;
+		self.get_vector(&job.pre_trust_id)?;
+		self.get_vector(&job.global_trust_id)?;
+
+		let id = self.generate_id();
+		self.jobs.insert(id.clone(), job);
+		Ok(id)
+	}
+
+	pub fn get_job(&self, id: &str) -> Result<&ComputeJob, CoreError> {
+		self.jobs.get(id).ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	pub fn delete_job(&mut self, id: &str) -> Result<(), CoreError> {
+		self.jobs.remove(id).map(|_| ()).ok_or_else(|| CoreError::NotFoundError(id.to_string()))
+	}
+
+	/// Matrices and vectors can't be deleted from under a compute job.
+	fn check_unused(&self, id: &str) -> Result<(), CoreError> {
+		match self.jobs.iter().find(|(_, job)| job.uses(id)) {
+			Some((job_id, _)) => Err(CoreError::InvalidParamsError(format!(
+				"{} is in use by compute job {}",
+				id, job_id
+			))),
+			None => Ok(()),
+		}
+	}
+
+	/// Inputs of the selected jobs for which an input at the given timestamp
+	/// belongs to a later window than their current result.
+	/// The result bears the starting timestamp of that window,
+	/// so it must be stored before the triggering input gets applied.
+	pub fn triggered_jobs(
+		&self, timestamp: Timestamp, filter: impl Fn(&ComputeJob) -> bool,
+	) -> Result<Vec<ComputeInput>, CoreError> {
+		let mut triggered = Vec::new();
+		for job in self.jobs.values().filter(|job| filter(job)) {
+			let window = timestamp.window_start(job.period);
+			if window > self.get_vector(&job.global_trust_id)?.timestamp() {
+				triggered.push(self.compute_input(
+					&job.local_trust_id, &job.pre_trust_id, &job.global_trust_id, &job.params,
+					window,
+				)?);
+			}
+		}
+		Ok(triggered)
+	}
+
+	/// Re-compute the triggered jobs in place, see `triggered_jobs`.
+	fn trigger_jobs(
+		&mut self, timestamp: Timestamp, filter: impl Fn(&ComputeJob) -> bool,
+	) -> Result<(), CoreError> {
+		for input in self.triggered_jobs(timestamp, filter)? {
+			let res = input.run()?;
+			self.store_result(input, &res)?;
+		}
+		Ok(())
+	}
+
+	/// Copy out the inputs of a compute of global trust from the given local trust and pre-trust,
+	/// starting from (and to be stored into) the given global trust vector.
+	/// The result bears the given timestamp.
+	pub fn compute_input(
+		&self, lt_id: &str, pt_id: &str, gt_id: &str, params: &ComputeParams, timestamp: Timestamp,
+	) -> Result<ComputeInput, CoreError> {
+		let lt = self.get_matrix(lt_id)?;
+		let pt = self.get_vector(pt_id)?;
+		let gt = self.get_vector(gt_id)?;
+
+		let mut dids = BTreeSet::new();
+		lt.entries().for_each(|(x, y, _)| {
+			dids.insert(x.clone());
+			dids.insert(y.clone());
+		});
+		pt.entries().chain(gt.entries()).for_each(|(x, _)| {
+			dids.insert(x.clone());
+		});
+		let dids: Vec<String> = dids.into_iter().collect();
+		let indices: HashMap<&String, u32> =
+			dids.iter().enumerate().map(|(i, did)| (did, i as u32)).collect();
+
+		let mut local_trust = LocalTrust::new(dids.len());
+		lt.entries().for_each(|(x, y, value)| local_trust.set(indices[x], indices[y], value));
+		let pre_trust: Vec<f64> = dids.iter().map(|x| pt.get(x)).collect();
+		let initial: Vec<f64> = dids.iter().map(|x| gt.get(x)).collect();
+
+		Ok(ComputeInput {
+			gt_id: gt_id.to_string(),
+			dids,
+			local_trust,
+			pre_trust,
+			initial,
+			params: params.clone(),
+			timestamp,
+		})
+	}
+
+	/// Store the result of a compute into its global trust vector,
+	/// unless a later one was stored in the meantime.
+	pub fn store_result(
+		&mut self, input: ComputeInput, res: &ComputeResult,
+	) -> Result<(), CoreError> {
+		let gt = self.get_vector_mut(&input.gt_id)?;
+		if gt.timestamp() > input.timestamp {
+			return Ok(());
+		}
+		let entries = input.dids.into_iter().zip(res.scores.iter().copied()).collect();
+		gt.flush();
+		gt.update(input.timestamp, entries)
+	}
+
+	/// Compute global trust from the given local trust and pre-trust,
+	/// starting from (and storing the result into) the given global trust vector.
+	/// The result bears the given timestamp.
+	pub fn compute(
+		&mut self, lt_id: &str, pt_id: &str, gt_id: &str, params: &ComputeParams,
+		timestamp: Timestamp,
+	) -> Result<ComputeResult, CoreError> {
+		let input = self.compute_input(lt_id, pt_id, gt_id, params, timestamp)?;
+		let res = input.run()?;
+		self.store_result(input, &res)?;
+		Ok(res)
+	}
+}
+
+/// Snapshot of the inputs of a compute, so that it runs without holding the store.
+#[derive(Debug, Clone)]
+pub struct ComputeInput {
+	gt_id: String,
+	dids: Vec<String>,
+	local_trust: LocalTrust,
+	pre_trust: Vec<f64>,
+	initial: Vec<f64>,
+	params: ComputeParams,
+	timestamp: Timestamp,
+}
+
+impl ComputeInput {
+	pub fn run(&self) -> Result<ComputeResult, CoreError> {
+		eigentrust(
+			&self.local_trust,
+			&self.pre_trust,
+			Some(&self.initial),
+			&self.params,
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_reject_updates_back_in_time() {
+		let mut store = Store::default();
+		let id = store.create_matrix();
+		let matrix = store.get_matrix_mut(&id).unwrap();
+
+		let entries = vec![("a".to_string(), "b".to_string(), 1.)];
+		matrix.update(Timestamp::new(10), entries.clone()).unwrap();
+		assert!(matrix.update(Timestamp::new(9), entries).is_err());
+		assert!(matrix.update(Timestamp::new(10), Vec::new()).is_ok());
+
+		store.delete_matrix(&id).unwrap();
+		assert!(store.get_matrix(&id).is_err());
+	}
+
+	#[test]
+	fn should_compute_into_global_trust_vector() {
+		let mut store = Store::default();
+		let lt_id = store.create_matrix();
+		let pt_id = store.create_vector();
+		let gt_id = store.create_vector();
+
+		let lt = store.get_matrix_mut(&lt_id).unwrap();
+		let entries = vec![
+			("alice".to_string(), "bob".to_string(), 1.),
+			("bob".to_string(), "alice".to_string(), 1.),
+			("carol".to_string(), "dave".to_string(), 1.),
+		];
+		lt.update(Timestamp::new(5), entries).unwrap();
+		let pt = store.get_vector_mut(&pt_id).unwrap();
+		pt.update(Timestamp::new(3), vec![("alice".to_string(), 1.)]).unwrap();
+
+		store
+			.compute(
+				&lt_id,
+				&pt_id,
+				&gt_id,
+				&ComputeParams::default(),
+				Timestamp::new(5),
+			)
+			.unwrap();
+
+		let gt = store.get_vector(&gt_id).unwrap();
+		assert_eq!(gt.timestamp(), Timestamp::new(5));
+		assert!(gt.get("alice") > 0. && gt.get("bob") > 0.);
+		assert_eq!(gt.get("carol"), 0.);
+		assert_eq!(gt.get("dave"), 0.);
+	}
+
+	#[test]
+	fn should_compute_from_snapshot() {
+		let mut store = Store::default();
+		let lt_id = store.create_matrix();
+		let pt_id = store.create_vector();
+		let gt_id = store.create_vector();
+		let entry = |x: &str, y: &str| vec![(x.to_string(), y.to_string(), 1.)];
+		store.update_matrix(&lt_id, Timestamp::new(5), entry("alice", "bob")).unwrap();
+
+		let params = ComputeParams::default();
+		let input =
+			store.compute_input(&lt_id, &pt_id, &gt_id, &params, Timestamp::new(5)).unwrap();
+		// Updates made while computing don't affect the result
+		store.update_matrix(&lt_id, Timestamp::new(6), entry("bob", "carol")).unwrap();
+		let res = input.run().unwrap();
+		store.store_result(input.clone(), &res).unwrap();
+
+		let gt = store.get_vector(&gt_id).unwrap();
+		assert_eq!(gt.timestamp(), Timestamp::new(5));
+		assert_eq!(gt.entries().count(), 2);
+		assert_eq!(gt.get("carol"), 0.);
+
+		// A stale result doesn't override a later one
+		store.compute(&lt_id, &pt_id, &gt_id, &params, Timestamp::new(6)).unwrap();
+		store.store_result(input, &res).unwrap();
+		let gt = store.get_vector(&gt_id).unwrap();
+		assert_eq!(gt.timestamp(), Timestamp::new(6));
+		assert!(gt.get("carol") > 0.);
+	}
+
+	#[test]
+	fn should_recompute_job_on_later_window() {
+		let mut store = Store::default();
+		let lt_id = store.create_matrix();
+		let pt_id = store.create_vector();
+		let gt_id = store.create_vector();
+		store.get_vector_mut(&gt_id).unwrap().update(Timestamp::new(9000), Vec::new()).unwrap();
+
+		let job = ComputeJob {
+			local_trust_id: lt_id.clone(),
+			pre_trust_id: pt_id.clone(),
+			global_trust_id: gt_id.clone(),
+			params: ComputeParams::default(),
+			period: Timestamp::new(1000),
+		};
+		let job_id = store.create_job(job).unwrap();
+		assert!(store.delete_matrix(&lt_id).is_err());
+
+		let entry = |x: &str, y: &str| vec![(x.to_string(), y.to_string(), 1.)];
+		let updates = [
+			(9947, entry("alice", "bob"), 9000),
+			(10814, entry("bob", "alice"), 10000),
+			(11438, entry("bob", "carol"), 11000),
+			(11975, entry("carol", "alice"), 11000),
+			(12000, entry("dave", "alice"), 12000),
+		];
+		for (timestamp, entries, expected) in updates {
+			store.update_matrix(&lt_id, Timestamp::new(timestamp), entries).unwrap();
+			let gt = store.get_vector(&gt_id).unwrap();
+			assert_eq!(gt.timestamp(), Timestamp::new(expected));
+		}
+
+		// The result at 12000 doesn't reflect the triggering input
+		let gt = store.get_vector(&gt_id).unwrap();
+		assert!(gt.get("carol") > 0.);
+		assert_eq!(gt.get("dave"), 0.);
+
+		store.delete_job(&job_id).unwrap();
+		store.update_matrix(&lt_id, Timestamp::new(13000), entry("erin", "alice")).unwrap();
+		let gt = store.get_vector(&gt_id).unwrap();
+		assert_eq!(gt.timestamp(), Timestamp::new(12000));
+		store.delete_matrix(&lt_id).unwrap();
+	}
+
+	#[test]
+	fn should_version_pre_trust_updates() {
+		let mut store = Store::default();
+		let id = store.create_pre_trust(2);
+		assert_eq!(id, pre_trust_id(2));
+
+		store.update_vector(&id, Timestamp::new(10), vec![("alice".to_string(), 3.)]).unwrap();
+		store.update_vector(&id, Timestamp::new(20), vec![("bob".to_string(), 1.)]).unwrap();
+
+		let pre_trust = store.get_pre_trust(2).unwrap();
+		assert_eq!(pre_trust.at(Timestamp::new(15)).unwrap().len(), 1);
+		let set = pre_trust.at(Timestamp::new(20)).unwrap();
+		assert_eq!(set["alice"], 0.75);
+		assert_eq!(set["bob"], 0.25);
+		assert!(store.get_pre_trust(1).is_err());
+	}
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use proto_buf::transformer::Form;
+
+use crate::combiner::NEW_DATA_BATCH_SIZE;
+use crate::domain::DomainConfig;
+use crate::error::CoreError;
+use crate::matrix::LocalTrust;
+use crate::pipeline::Pipeline;
+
+/// Local trust and DID mappings, as served by the linear combiner.
+#[tonic::async_trait]
+pub trait LtSource {
+	/// Read (and consume) a batch of updated entries.
+	async fn get_new_batch(
+		&mut self, domain: u32, form: Form,
+	) -> Result<Vec<(u32, u32, f64)>, CoreError>;
+
+	async fn get_did_mapping(&mut self) -> Result<BTreeMap<u32, String>, CoreError>;
+
+	async fn get_local_trust(
+		&mut self, domain: u32, form: Form, size: u32,
+	) -> Result<LocalTrust, CoreError>;
+}
+
+/// Inputs of an independent per-domain compute.
+#[derive(Debug, Clone)]
+pub struct DomainRun {
+	pub config: DomainConfig,
+	pub pipeline: Pipeline,
+	/// Whether the inputs changed since the last successful compute.
+	pub is_changed: bool,
+}
+
+impl DomainRun {
+	pub fn new(config: DomainConfig) -> Self {
+		Self { config, pipeline: Pipeline::default(), is_changed: false }
+	}
+
+	fn update(&mut self, form: Form, entries: Vec<(u32, u32, f64)>) {
+		if entries.is_empty() {
+			return;
+		}
+		match form {
+			Form::Trust => self.pipeline.update_trust(entries),
+			Form::Distrust => self.pipeline.update_distrust(entries),
+		}
+		self.is_changed = true;
+	}
+}
+
+/// Bring the pipelines up to date with the linear combiner.
+/// The first sync reads the full matrices, later ones only the updated entries.
+/// Returns whether any of the domains has to be computed again.
+pub async fn sync<S: LtSource + Send>(
+	source: &mut S, runs: &mut [DomainRun], is_initial: bool,
+) -> Result<bool, CoreError> {
+	// The combiner drops the updates it hands out, so each batch is applied as soon as it
+	// arrives, and is kept even if a later call fails.
+	// Updates are consumed before the mapping, so that it covers all of them.
+	for run in runs.iter_mut() {
+		for form in [Form::Trust, Form::Distrust] {
+			loop {
+				let entries = source.get_new_batch(run.config.domain, form).await?;
+				let is_last = entries.len() < NEW_DATA_BATCH_SIZE as usize;
+				run.update(form, entries);
+				if is_last {
+					break;
+				}
+			}
+		}
+	}
+
+	let mapping = source.get_did_mapping().await?;
+	let size = mapping.keys().last().map_or(0, |x| x + 1);
+
+	for run in runs.iter_mut() {
+		run.pipeline.set_mapping(mapping.clone());
+		if is_initial {
+			let domain = run.config.domain;
+			let trust = source.get_local_trust(domain, Form::Trust, size).await?;
+			let distrust = source.get_local_trust(domain, Form::Distrust, size).await?;
+			run.pipeline.update_trust(trust.entries().collect());
+			run.pipeline.update_distrust(distrust.entries().collect());
+			run.is_changed = true;
+		}
+	}
+	Ok(runs.iter().any(|x| x.is_changed))
+}
+
+#[cfg(test)]
+mod test {
+	use std::collections::VecDeque;
+
+	use super::*;
+
+	/// Hands out the queued batches, failing once they run out.
+	struct MockSource {
+		batches: VecDeque<Vec<(u32, u32, f64)>>,
+	}
+
+	#[tonic::async_trait]
+	impl LtSource for MockSource {
+		async fn get_new_batch(
+			&mut self, _domain: u32, _form: Form,
+		) -> Result<Vec<(u32, u32, f64)>, CoreError> {
+			self.batches
+				.pop_front()
+				.ok_or_else(|| CoreError::GrpcError(tonic::Status::unavailable("down")))
+		}
+
+		async fn get_did_mapping(&mut self) -> Result<BTreeMap<u32, String>, CoreError> {
+			Ok(BTreeMap::from([
+				(0, "alice".to_string()),
+				(1, "bob".to_string()),
+			]))
+		}
+
+		async fn get_local_trust(
+			&mut self, _domain: u32, _form: Form, size: u32,
+		) -> Result<LocalTrust, CoreError> {
+			Ok(LocalTrust::new(size as usize))
+		}
+	}
+
+	#[test]
+	fn should_keep_consumed_updates_when_a_later_call_fails() {
+		let rt = tokio::runtime::Runtime::new().unwrap();
+		let mut runs = vec![DomainRun::new(DomainConfig::new(1, "SoftwareDevelopment"))];
+
+		// The trust update is consumed, then reading the distrust ones fails
+		let mut source = MockSource { batches: VecDeque::from([vec![(0, 1, 50.)]]) };
+		assert!(rt.block_on(sync(&mut source, &mut runs, false)).is_err());
+		assert!(runs[0].is_changed);
+
+		// Once the combiner is back, without any new update, the consumed one is computed
+		let mut source = MockSource { batches: VecDeque::from([Vec::new(), Vec::new()]) };
+		assert!(rt.block_on(sync(&mut source, &mut runs, false)).unwrap());
+		assert_eq!(
+			runs[0].pipeline.trust().entries().collect::<Vec<_>>(),
+			vec![(0, 1, 50.)]
+		);
+	}
+}
@@ -0,0 +1,81 @@
+use crate::error::CoreError;
+
+/// Timestamp of a trust matrix/vector, as transferred in `timestamp_qwords`.
+/// The qwords form a variable-length big endian unsigned integer,
+/// e.g. {0x1234, 0x567890abcdef} -> 0x12340000567890abcdef.
+/// Up to two significant qwords are supported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(u128);
+
+impl Timestamp {
+	pub fn new(value: u128) -> Self {
+		Self(value)
+	}
+
+	pub fn value(&self) -> u128 {
+		self.0
+	}
+
+	/// Start of the time window of the given period which this timestamp falls into.
+	pub fn window_start(&self, period: Timestamp) -> Timestamp {
+		Self(self.0 - self.0 % period.0)
+	}
+
+	pub fn from_qwords(qwords: &[u64]) -> Result<Self, CoreError> {
+		let significant: Vec<u64> = qwords.iter().copied().skip_while(|x| *x == 0).collect();
+		if significant.len() > 2 {
+			return Err(CoreError::InvalidParamsError(format!(
+				"timestamp of {} qwords is too large",
+				significant.len()
+			)));
+		}
+		let value = significant.iter().fold(0u128, |acc, x| (acc << 64) | u128::from(*x));
+		Ok(Self(value))
+	}
+
+	pub fn to_qwords(&self) -> Vec<u64> {
+		let high = (self.0 >> 64) as u64;
+		let low = self.0 as u64;
+		if high == 0 {
+			vec![low]
+		} else {
+			vec![high, low]
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_convert_qwords() {
+		let ts = Timestamp::from_qwords(&[0, 0x1234, 0x567890abcdef]).unwrap();
+		assert_eq!(ts.value(), 0x12340000567890abcdef);
+		assert_eq!(ts.to_qwords(), vec![0x1234, 0x567890abcdef]);
+
+		assert_eq!(Timestamp::from_qwords(&[]).unwrap(), Timestamp::default());
+		assert_eq!(Timestamp::new(9000).to_qwords(), vec![9000]);
+		assert!(Timestamp::from_qwords(&[1, 0, 0]).is_err());
+	}
+
+	#[test]
+	fn should_compare_timestamps() {
+		let older = Timestamp::from_qwords(&[5, u64::MAX]).unwrap();
+		let newer = Timestamp::from_qwords(&[6, 0]).unwrap();
+		assert!(older < newer);
+	}
+
+	#[test]
+	fn should_find_window_start() {
+		let period = Timestamp::new(1000);
+		assert_eq!(
+			Timestamp::new(9947).window_start(period),
+			Timestamp::new(9000)
+		);
+		assert_eq!(
+			Timestamp::new(12000).window_start(period),
+			Timestamp::new(12000)
+		);
+	}
+}
@@ -2,11 +2,15 @@ use clap::Parser as ClapParser;
 
 #[derive(ClapParser)]
 pub struct Args {
-	/// Input CSV file.
-	#[arg(
-		long,
-		value_name = "FILE",
-		default_value = "./scripts/generate_mock_attestations/output/output.csv"
-	)]
-	pub csv: String,
+	/// Input CSV files, each indexed by its own task. None are indexed by default.
+	#[arg(long, value_name = "FILE")]
+	pub csv: Vec<String>,
+
+	/// Source address the records of the CSV files are served under.
+	#[arg(long, value_name = "ADDRESS", default_value = "0x1")]
+	pub csv_source_address: String,
+
+	/// Index the Clique registry configured in the environment, alongside the server.
+	#[arg(long)]
+	pub clique: bool,
 }
@@ -1,14 +1,16 @@
 use std::cmp;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::error::Error;
 use std::sync::Arc;
 
-use ethers::abi::RawLog;
-use ethers::contract::decode_logs;
-use ethers::core::types::{Address, Filter};
+use ethers::abi::{AbiDecode, RawLog};
+use ethers::contract::EthLogDecode;
+use ethers::core::types::{Address, Filter, H256};
 use ethers::prelude::abigen;
 use ethers::providers::{Http, Middleware, Provider};
 use eyre::Result;
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::clients::clique::types::EVMIndexerConfig;
 
@@ -25,6 +27,37 @@ abigen!(
 	event_derives (serde::Deserialize, serde::Serialize);
 );
 
+/// Registry event affecting an attestation.
+#[derive(Clone, Debug)]
+pub enum CliqueEvent {
+	Recorded(Attestation),
+	Updated(UpdateRequest),
+	Revoked([u8; 32]),
+}
+
+/// Registry event, located on-chain.
+#[derive(Clone, Debug)]
+pub struct CliqueEventLog {
+	pub event: CliqueEvent,
+	pub block_number: u64,
+	pub block_hash: H256,
+	// block timestamp, in seconds
+	pub timestamp: u64,
+	pub tx_hash: H256,
+	pub log_index: u64,
+	// position of the event in a batch log
+	pub batch_index: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct CliqueQueryResult {
+	pub logs: Vec<CliqueEventLog>,
+	// last block queried
+	pub to_block: u64,
+	// latest block with enough confirmations
+	pub confirmed_block: u64,
+}
+
 impl CliqueClient {
 	pub fn new(config: EVMIndexerConfig) -> Self {
 		let provider = Provider::<Http>::try_from(config.rpc_url.clone()).unwrap();
@@ -36,40 +69,108 @@ impl CliqueClient {
 		CliqueClient { config, contract }
 	}
 
-	pub async fn query(&self, from: Option<u64>, range: Option<u64>) -> Result<(), Box<dyn Error>> {
+	/// Registry events in the block range, with the batch variants flattened,
+	/// along with the last block of the range, capped at the latest confirmed block.
+	/// Blocks are confirmed once `confirmations` blocks were mined on top of them.
+	pub async fn query(
+		&self, from: Option<u64>, range: Option<u64>,
+	) -> Result<CliqueQueryResult, Box<dyn Error>> {
 		let config = &self.config;
-		let contract_address = &config.master_registry_contract;
+		let contract_address: Address = config.master_registry_contract.parse()?;
 
 		let block_range = range.unwrap_or(DEFAULT_BLOCK_RANGE);
 		let from_block = from.unwrap_or(config.from_block);
-		let latest_onchain_block =
-			self.contract.client_ref().get_block_number().await.unwrap().as_u64();
-
-		let to_block = cmp::min(from_block + block_range - 1, latest_onchain_block);
-
-		let filter = Filter::new()
-			.address(vec![contract_address.parse().unwrap()])
-			.from_block(from_block)
-			.to_block(to_block);
-
-		let logs = self.contract.client_ref().get_logs(&filter).await.unwrap();
+		let latest_block = self.contract.client_ref().get_block_number().await?.as_u64();
+		let confirmed_block = latest_block.saturating_sub(config.confirmations);
+		if from_block > confirmed_block {
+			return Ok(CliqueQueryResult {
+				logs: Vec::new(),
+				to_block: confirmed_block,
+				confirmed_block,
+			});
+		}
 
-		let raw_logs: Vec<RawLog> = logs
-			.into_iter()
-			.map(|log| RawLog { topics: log.topics, data: log.data.to_vec() })
-			.collect();
+		let to_block = cmp::min(from_block + block_range - 1, confirmed_block);
+
+		let filter =
+			Filter::new().address(vec![contract_address]).from_block(from_block).to_block(to_block);
+
+		let logs = self.contract.client_ref().get_logs(&filter).await?;
+
+		let mut timestamps = HashMap::new();
+		let mut event_logs = Vec::new();
+		for log in logs {
+			let tx_hash = log.transaction_hash.unwrap_or_default();
+			let log_index = log.log_index.unwrap_or_default().as_u64();
+			let block_number = log.block_number.unwrap_or_default().as_u64();
+			let block_hash = log.block_hash.unwrap_or_default();
+			let raw_log = RawLog { topics: log.topics, data: log.data.to_vec() };
+
+			let events = match CLIQUEEvents::decode_log(&raw_log)? {
+				CLIQUEEvents::AttestationRecordedFilter(x) => {
+					vec![CliqueEvent::Recorded(x.attestation)]
+				},
+				CLIQUEEvents::BatchAttestationRecordedFilter(x) => {
+					x.attestations.into_iter().map(CliqueEvent::Recorded).collect()
+				},
+				CLIQUEEvents::AttestationUpdatedFilter(x) => {
+					vec![CliqueEvent::Updated(x.update_request)]
+				},
+				CLIQUEEvents::BatchAttestationUpdatedFilter(x) => {
+					x.update_requests.into_iter().map(CliqueEvent::Updated).collect()
+				},
+				CLIQUEEvents::AttestationRevokedFilter(x) => {
+					vec![CliqueEvent::Revoked(x.attestation_id)]
+				},
+				// The log only holds the hash of the indexed ids, which are read from the call
+				CLIQUEEvents::BatchAttestationRevokedFilter(_) => {
+					match self.revoked_ids(tx_hash).await? {
+						Some(ids) => ids.into_iter().map(CliqueEvent::Revoked).collect(),
+						None => {
+							warn!("Skipping batch revocation not called on the registry");
+							Vec::new()
+						},
+					}
+				},
+				_ => Vec::new(),
+			};
+			if events.is_empty() {
+				continue;
+			}
+
+			if let Entry::Vacant(entry) = timestamps.entry(block_number) {
+				let block = self.contract.client_ref().get_block(block_number).await?;
+				let block = block.ok_or_else(|| format!("Block {} not found", block_number))?;
+				entry.insert(block.timestamp.as_u64());
+			}
+			let timestamp = timestamps[&block_number];
+
+			for (batch_index, event) in events.into_iter().enumerate() {
+				event_logs.push(CliqueEventLog {
+					event,
+					block_number,
+					block_hash,
+					timestamp,
+					tx_hash,
+					log_index,
+					batch_index,
+				});
+			}
+		}
 
-		let attestations: Vec<Attestation> = decode_logs::<AttestationRecordedFilter>(&raw_logs)
-			.unwrap()
-			.into_iter()
-			.map(|a| a.attestation)
-			.collect();
+		Ok(CliqueQueryResult { logs: event_logs, to_block, confirmed_block })
+	}
 
-		// todo broadcast the chunk
-		for a in attestations {
-			println!("{:?}", a);
-		}
+	/// Hash of the block currently at the given height, if any.
+	pub async fn block_hash(&self, number: u64) -> Result<Option<H256>, Box<dyn Error>> {
+		let block = self.contract.client_ref().get_block(number).await?;
+		Ok(block.and_then(|x| x.hash))
+	}
 
-		Ok(())
+	/// Attestation IDs of a `revokeBatch` transaction, if it directly called the registry.
+	async fn revoked_ids(&self, tx_hash: H256) -> Result<Option<Vec<[u8; 32]>>, Box<dyn Error>> {
+		let tx = self.contract.client_ref().get_transaction(tx_hash).await?;
+		let call = tx.and_then(|tx| RevokeBatchCall::decode(&tx.input).ok());
+		Ok(call.map(|x| x.attestation_ids))
 	}
 }
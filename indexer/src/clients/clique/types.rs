@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 // todo rename to clique specific
 #[derive(Clone, Debug)]
 pub struct EVMIndexerConfig {
@@ -5,4 +7,8 @@ pub struct EVMIndexerConfig {
 	pub rpc_url: String,
 	pub master_registry_contract: String,
 	pub from_block: u64,
+	// blocks mined on top of a block before it is indexed
+	pub confirmations: u64,
+	// registry schema id, as lowercase 0x-hex, to the schema id of the task responses
+	pub schema_ids: HashMap<String, usize>,
 }
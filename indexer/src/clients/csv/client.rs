@@ -19,6 +19,10 @@ impl CSVClient {
 		CSVClient { config }
 	}
 
+	pub fn get_path(&self) -> &str {
+		&self.config.path
+	}
+
 	pub async fn query(
 		&self, from: Option<u64>, range: Option<u64>,
 	) -> Result<Vec<Result<StringRecord, csv::Error>>, Box<dyn Error>> {
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 
 use dotenv::dotenv;
@@ -7,6 +8,8 @@ use crate::clients::clique::types::EVMIndexerConfig;
 use crate::frontends::api::grpc_server::types::GRPCServerConfig;
 use crate::storage::lm_db::types::LMDBClientConfig;
 
+const DEFAULT_CONFIRMATIONS: u64 = 12;
+
 // types to components
 #[derive(Clone, Debug)]
 pub struct LoggerConfig {
@@ -32,6 +35,19 @@ fn parse_level_from_string(level: &str) -> Option<Level> {
 	}
 }
 
+/// Parse `<registry schema id>=<schema id>` pairs, separated by commas.
+fn parse_schema_ids(value: &str) -> Option<HashMap<String, usize>> {
+	value
+		.split(',')
+		.filter(|x| !x.trim().is_empty())
+		.map(|pair| {
+			let (key, id) = pair.split_once('=')?;
+			let id = id.trim().parse::<usize>().ok()?;
+			Some((key.trim().to_lowercase(), id))
+		})
+		.collect()
+}
+
 impl Config {
 	pub fn from_env() -> Self {
 		dotenv().ok();
@@ -47,6 +63,15 @@ impl Config {
 		let master_registry_contract = env::var("CLIQUE_EVM_INDEXER_MASTER_REGISTRY_ADDRESS")
 			.expect("CLIQUE_EVM_INDEXER_MASTER_REGISTRY_ADDRESS not found in .env");
 
+		let confirmations = env::var("CLIQUE_EVM_INDEXER_CONFIRMATIONS")
+			.unwrap_or(DEFAULT_CONFIRMATIONS.to_string())
+			.parse::<u64>()
+			.expect("Invalid CLIQUE_EVM_INDEXER_CONFIRMATIONS");
+
+		let schema_ids =
+			parse_schema_ids(&env::var("CLIQUE_EVM_INDEXER_SCHEMA_IDS").unwrap_or_default())
+				.expect("Invalid CLIQUE_EVM_INDEXER_SCHEMA_IDS");
+
 		let logger_level_str = env::var("LOGGER_LEVEL").unwrap_or("info".to_string());
 		let logger_level = parse_level_from_string(&logger_level_str).unwrap();
 
@@ -55,7 +80,13 @@ impl Config {
 		let grpc_server_port: u16 =
 			env::var("GRPC_SERVER_PORT").unwrap_or(50050.to_string()).parse::<u16>().unwrap();
 
-		let evm_indexer_config = EVMIndexerConfig { rpc_url, from_block, master_registry_contract };
+		let evm_indexer_config = EVMIndexerConfig {
+			rpc_url,
+			from_block,
+			confirmations,
+			master_registry_contract,
+			schema_ids,
+		};
 
 		let logger_config = LoggerConfig { logger_level };
 
@@ -65,9 +96,27 @@ impl Config {
 			path: lm_db_path,
 			db_name: "indexer".to_string(),
 			max_dbs: 3000,
-			map_size: 10 * 1024 * 1024,
+			map_size: 1024 * 1024 * 1024,
 		};
 
 		Config { evm_indexer_config, logger_config, grpc_server_config, lm_db_config }
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_parse_schema_ids() {
+		let schema_ids = parse_schema_ids(" 0xAB=2, 0xcd = 0,").unwrap();
+		assert_eq!(
+			schema_ids,
+			HashMap::from([("0xab".to_string(), 2), ("0xcd".to_string(), 0)])
+		);
+		assert_eq!(parse_schema_ids(""), Some(HashMap::new()));
+
+		assert_eq!(parse_schema_ids("0xab"), None);
+		assert_eq!(parse_schema_ids("0xab=trust"), None);
+	}
+}
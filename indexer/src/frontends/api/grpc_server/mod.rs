@@ -1,34 +1,102 @@
 use std::cmp;
 use std::error::Error;
-use std::time::SystemTime;
 
-use tokio::sync::mpsc::channel;
+use tokio::sync::mpsc::{channel, Sender};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::transport::Server;
 use tonic::{Request, Response, Status};
 use tracing::info;
 
 use proto_buf::indexer::indexer_server::{Indexer, IndexerServer};
-use proto_buf::indexer::{IndexerEvent, Query};
+use proto_buf::indexer::{EventKind as EventKindPb, IndexerEvent, Query};
 
 use crate::frontends::api::grpc_server::types::GRPCServerConfig;
-use crate::tasks::service::TaskService;
+use crate::storage::lm_db::LMDBClient;
 use crate::tasks::types::TaskResponse;
 
 pub mod types;
 
+// records read from storage at once, when following
+const FOLLOW_BATCH_SIZE: usize = 100;
+
 pub struct IndexerService {
-	data: Vec<TaskResponse>,
+	db: LMDBClient,
 }
 
 pub struct GRPCServer {
 	config: GRPCServerConfig,
-	task_service: TaskService,
+	db: LMDBClient,
 }
 
 impl IndexerService {
-	fn new(data: Vec<TaskResponse>) -> Self {
-		IndexerService { data }
+	fn new(db: LMDBClient) -> Self {
+		IndexerService { db }
+	}
+}
+
+/// Schema id given either as 0x-hex or as a decimal.
+fn parse_schema_id(value: &str) -> Result<u32, Status> {
+	let res = match value.strip_prefix("0x") {
+		Some(hex) => u32::from_str_radix(hex, 16),
+		None => value.parse::<u32>(),
+	};
+	res.map_err(|_| Status::invalid_argument(format!("Invalid `schema_id`: {}", value)))
+}
+
+// ids are sequence ids, the same for every filter
+fn to_event(seq_id: u64, record: TaskResponse) -> IndexerEvent {
+	IndexerEvent {
+		id: seq_id,
+		schema_id: record.schema_id as u32,
+		schema_value: record.data,
+		timestamp: record.timestamp.parse::<u64>().unwrap(),
+		kind: EventKindPb::from(record.kind).into(),
+		attestation_id: record.attestation_id,
+		source_address: record.source_address,
+		job_id: record.job_id,
+		retraction: record.retraction,
+	}
+}
+
+/// Stream the matching events from the query offset, then the new ones as they are indexed.
+/// Storage is read no faster than the client consumes the bounded channel.
+/// Unlike a one-off query, a count of 0 doesn't limit the number of events.
+async fn follow(
+	db: LMDBClient, query: Query, schema_ids: Vec<u32>, tx: Sender<Result<IndexerEvent, Status>>,
+) {
+	// Subscribed before the first read, so that no append is missed
+	let mut updates = db.subscribe();
+	let mut offset = query.offset as usize;
+	let mut remaining = if query.count == 0 { usize::MAX } else { query.count as usize };
+
+	while remaining > 0 {
+		let count = cmp::min(remaining, FOLLOW_BATCH_SIZE);
+		let res = db
+			.get_events(&query.source_address, &schema_ids, offset, count)
+			.map_err(|e| Status::internal(format!("Internal error: {}", e)));
+		let records = match res {
+			Ok(records) => records,
+			Err(status) => {
+				let _ = tx.send(Err(status)).await;
+				return;
+			},
+		};
+
+		if records.is_empty() {
+			tokio::select! {
+				res = updates.changed() => if res.is_err() { return },
+				_ = tx.closed() => return,
+			}
+			continue;
+		}
+
+		offset += records.len();
+		remaining -= records.len();
+		for (seq_id, record) in records {
+			if tx.send(Ok(to_event(seq_id, record))).await.is_err() {
+				return;
+			}
+		}
 	}
 }
 
@@ -41,29 +109,32 @@ impl Indexer for IndexerService {
 	) -> Result<Response<Self::SubscribeStream>, Status> {
 		let inner = request.into_inner();
 
-		let _start = SystemTime::now();
-		// let current_secs = _start.duration_since(UNIX_EPOCH).expect("Time went backwards").as_secs();
-		let limit = cmp::min(
-			inner.offset + inner.count,
-			self.data.len().try_into().unwrap(),
-		);
-
-		let data = self.data.clone();
+		let schema_ids = inner
+			.schema_id
+			.iter()
+			.map(|x| parse_schema_id(x))
+			.collect::<Result<Vec<u32>, Status>>()?;
 
 		let (tx, rx) = channel(4);
+		if inner.follow {
+			tokio::spawn(follow(self.db.clone(), inner, schema_ids, tx));
+			return Ok(Response::new(ReceiverStream::new(rx)));
+		}
+
+		// Offsets count the matching events only
+		let records = self
+			.db
+			.get_events(
+				&inner.source_address, &schema_ids, inner.offset as usize, inner.count as usize,
+			)
+			.map_err(|e| Status::internal(format!("Internal error: {}", e)))?;
+
 		tokio::spawn(async move {
-			for i in inner.offset..limit {
-				let index: usize = i as usize;
-
-				let record = data[index].clone();
-
-				let event = IndexerEvent {
-					id: i + 1,
-					schema_id: record.schema_id as u32,
-					schema_value: record.data,
-					timestamp: record.timestamp.parse::<u64>().unwrap(),
-				};
-				tx.send(Ok(event)).await.unwrap();
+			for (seq_id, record) in records {
+				// The client dropped the stream
+				if tx.send(Ok(to_event(seq_id, record))).await.is_err() {
+					break;
+				}
 			}
 		});
 
@@ -72,21 +143,120 @@ impl Indexer for IndexerService {
 }
 
 impl GRPCServer {
-	pub fn new(config: GRPCServerConfig, task_service: TaskService) -> Self {
-		GRPCServer { config, task_service }
+	pub fn new(config: GRPCServerConfig, db: LMDBClient) -> Self {
+		GRPCServer { config, db }
 	}
 
 	pub async fn serve(&mut self) -> Result<(), Box<dyn Error>> {
 		let address = format!("{}{}", "[::1]:", self.config.port).parse()?;
 		info!("GRPC server is starting at {}", address);
-		self.task_service.run().await;
 
-		// todo
-		let data = self.task_service.get_chunk(0, 10000).await;
-
-		let indexer_server = IndexerServer::new(IndexerService::new(data));
+		let indexer_server = IndexerServer::new(IndexerService::new(self.db.clone()));
 		Server::builder().add_service(indexer_server).serve(address).await?;
 
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use std::env;
+	use std::fs;
+	use std::process;
+	use std::time::Duration;
+
+	use tokio::sync::mpsc::Receiver;
+	use tokio::time::timeout;
+
+	use super::*;
+	use crate::storage::lm_db::types::LMDBClientConfig;
+	use crate::storage::types::BaseKVStorage;
+
+	const TIMEOUT: Duration = Duration::from_secs(5);
+
+	fn config(test_name: &str) -> LMDBClientConfig {
+		let path = env::temp_dir().join(format!("indexer-grpc-{}-{}", process::id(), test_name));
+		let _ = fs::remove_dir_all(&path);
+		LMDBClientConfig {
+			db_name: "indexer".to_string(),
+			path: path.to_string_lossy().into_owned(),
+			max_dbs: 10,
+			map_size: 10 * 1024 * 1024,
+		}
+	}
+
+	fn record(id: usize) -> TaskResponse {
+		TaskResponse {
+			id,
+			timestamp: "0".to_string(),
+			job_id: String::new(),
+			data: String::new(),
+			schema_id: 2,
+			source_address: "0xa".to_string(),
+			kind: Default::default(),
+			attestation_id: String::new(),
+			retraction: false,
+		}
+	}
+
+	async fn next_id(rx: &mut Receiver<Result<IndexerEvent, Status>>) -> Option<u64> {
+		let event = timeout(TIMEOUT, rx.recv()).await.expect("no event received")?;
+		Some(event.unwrap().id)
+	}
+
+	#[tokio::test]
+	async fn should_follow_appended_events_until_dropped() {
+		let config = config("follow");
+		let db = LMDBClient::new(config.clone());
+		db.append("task", "1", &[record(1), record(2)]).unwrap();
+
+		let (tx, mut rx) = channel(4);
+		let query = Query { offset: 1, count: 0, follow: true, ..Query::default() };
+		let handle = tokio::spawn(follow(db.clone(), query, Vec::new(), tx));
+		assert_eq!(next_id(&mut rx).await, Some(2));
+
+		// Records appended after subscribing are pushed
+		db.append("task", "2", &[record(3)]).unwrap();
+		assert_eq!(next_id(&mut rx).await, Some(3));
+
+		// The stream ends once the client drops it
+		drop(rx);
+		timeout(TIMEOUT, handle).await.expect("stream kept open").unwrap();
+
+		drop(db);
+		fs::remove_dir_all(config.path).unwrap();
+	}
+
+	#[tokio::test]
+	async fn should_follow_up_to_count_events() {
+		let config = config("follow-count");
+		let db = LMDBClient::new(config.clone());
+		db.append("task", "1", &[record(1), record(2)]).unwrap();
+
+		let (tx, mut rx) = channel(4);
+		let query = Query { count: 3, follow: true, ..Query::default() };
+		let handle = tokio::spawn(follow(db.clone(), query, vec![2], tx));
+		assert_eq!(next_id(&mut rx).await, Some(1));
+		assert_eq!(next_id(&mut rx).await, Some(2));
+
+		db.append("task", "2", &[record(3), record(4)]).unwrap();
+		assert_eq!(next_id(&mut rx).await, Some(3));
+		assert_eq!(next_id(&mut rx).await, None);
+		timeout(TIMEOUT, handle).await.expect("stream kept open").unwrap();
+
+		drop(db);
+		fs::remove_dir_all(config.path).unwrap();
+	}
+
+	#[test]
+	fn should_parse_schema_id() {
+		assert_eq!(parse_schema_id("0x2").unwrap(), 2);
+		assert_eq!(parse_schema_id("0x1f").unwrap(), 31);
+		assert_eq!(parse_schema_id("31").unwrap(), 31);
+
+		assert!(parse_schema_id("").is_err());
+		assert!(parse_schema_id("0x").is_err());
+		assert!(parse_schema_id("0xg").is_err());
+		assert!(parse_schema_id("-1").is_err());
+	}
+}
@@ -1,13 +1,15 @@
 use clap::Parser;
 use tracing::info;
 
+use crate::clients::clique::client::CliqueClient;
 use crate::clients::csv::{client::CSVClient, types::CSVClientConfig};
 use crate::config::dotenv::Config;
 use crate::frontends::api::grpc_server::GRPCServer;
 use crate::logger::global::AppLogger;
 use crate::storage::lm_db::LMDBClient;
+use crate::tasks::clique::task::CliqueTask;
 use crate::tasks::csv_poc::task::CSVPOCTask;
-use crate::tasks::service::TaskService;
+use crate::tasks::supervisor::TaskSupervisor;
 
 pub mod cli;
 pub mod clients;
@@ -32,23 +34,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 	let lm_db_config = config.lm_db_config;
 	let db = LMDBClient::new(lm_db_config);
 
-	let csv_client_config = CSVClientConfig { path: args.csv.clone() };
-	let csv_client = CSVClient::new(csv_client_config);
-	let csv_poc_task = CSVPOCTask::new(csv_client);
-
-	let task_service: TaskService = TaskService::new(Box::new(csv_poc_task), Box::new(db.clone()));
-
-	// let client_config = config.evm_indexer_config.clone();
-	// let client = CliqueClient::new(client_config);
-
-	// let clique_task_config = config.evm_indexer_config;
-	// let clique_task = CliqueTask::new(clique_task_config, client);
-
-	// let mut task_service = TaskService::new(Box::new(clique_task), Box::new(db));
-	// task_service.run().await;
-
+	let mut supervisor = TaskSupervisor::new();
+	for path in &args.csv {
+		let csv_client = CSVClient::new(CSVClientConfig { path: path.clone() });
+		let csv_poc_task = CSVPOCTask::new(csv_client, args.csv_source_address.to_lowercase());
+		supervisor.add(Box::new(csv_poc_task), Box::new(db.clone()))?;
+	}
+	if args.clique {
+		let client = CliqueClient::new(config.evm_indexer_config.clone());
+		let clique_task = CliqueTask::new(
+			config.evm_indexer_config.clone(),
+			client,
+			Box::new(db.clone()),
+		);
+		supervisor.add(Box::new(clique_task), Box::new(db.clone()))?;
+	}
+
+	// Records are served as soon as they are indexed
 	let grpc_server_config = config.grpc_server_config;
-	let mut server = GRPCServer::new(grpc_server_config, task_service);
-	server.serve().await?;
+	let mut server = GRPCServer::new(grpc_server_config, db.clone());
+	let (_, res) = tokio::join!(supervisor.run(), server.serve());
+	res?;
 	Ok(())
 }
@@ -0,0 +1,97 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::iter;
+
+use crate::tasks::types::TaskResponse;
+
+/// Sequence ids of the stored events, by source address and schema id,
+/// so that a filtered stream is read without scanning the others.
+#[derive(Clone, Debug, Default)]
+pub struct EventIndex {
+	seq_ids: BTreeMap<(String, u32), Vec<u64>>,
+}
+
+impl EventIndex {
+	/// Record the event under the given sequence id, which must be past the previous ones.
+	pub fn insert(&mut self, seq_id: u64, record: &TaskResponse) {
+		let key = (
+			record.source_address.to_lowercase(),
+			record.schema_id as u32,
+		);
+		self.seq_ids.entry(key).or_default().push(seq_id);
+	}
+
+	/// Sequence ids of the events matching the filters, in storage order,
+	/// skipping the first `offset` of them.
+	/// An empty source address, or an empty schema id list, matches any.
+	/// Since events are only appended, an offset into a filtered stream stays valid.
+	pub fn query(
+		&self, source_address: &str, schema_ids: &[u32], offset: usize, count: usize,
+	) -> Vec<u64> {
+		let source_address = source_address.to_lowercase();
+		let lists: Vec<&[u64]> = self
+			.seq_ids
+			.iter()
+			.filter(|((source, schema_id), _)| {
+				(source_address.is_empty() || *source == source_address)
+					&& (schema_ids.is_empty() || schema_ids.contains(schema_id))
+			})
+			.map(|(_, seq_ids)| seq_ids.as_slice())
+			.collect();
+
+		// Lazy merge of the sorted lists, by their next sequence id
+		let mut heads: BinaryHeap<Reverse<(u64, usize, usize)>> = lists
+			.iter()
+			.enumerate()
+			.filter_map(|(i, list)| list.first().map(|seq_id| Reverse((*seq_id, i, 0))))
+			.collect();
+		let merged = iter::from_fn(|| {
+			let Reverse((seq_id, i, pos)) = heads.pop()?;
+			if let Some(next) = lists[i].get(pos + 1) {
+				heads.push(Reverse((*next, i, pos + 1)));
+			}
+			Some(seq_id)
+		});
+		merged.skip(offset).take(count).collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn record(source_address: &str, schema_id: usize) -> TaskResponse {
+		TaskResponse {
+			id: 0,
+			timestamp: "0".to_string(),
+			job_id: String::new(),
+			data: String::new(),
+			schema_id,
+			source_address: source_address.to_string(),
+			kind: Default::default(),
+			attestation_id: String::new(),
+			retraction: false,
+		}
+	}
+
+	#[test]
+	fn should_query_events_in_storage_order() {
+		let mut index = EventIndex::default();
+		let records = [("0xA", 0), ("0xb", 1), ("0xa", 2), ("0xa", 0), ("0xb", 2), ("0xa", 1)];
+		for (i, (source_address, schema_id)) in records.into_iter().enumerate() {
+			index.insert(i as u64 + 1, &record(source_address, schema_id));
+		}
+
+		assert_eq!(index.query("", &[], 0, usize::MAX), vec![1, 2, 3, 4, 5, 6]);
+		assert_eq!(index.query("0xa", &[], 0, usize::MAX), vec![1, 3, 4, 6]);
+		assert_eq!(index.query("0xA", &[0, 2], 0, usize::MAX), vec![1, 3, 4]);
+		assert_eq!(index.query("", &[2, 1], 0, usize::MAX), vec![2, 3, 5, 6]);
+		assert!(index.query("0xc", &[], 0, usize::MAX).is_empty());
+
+		// Offsets count the matching events only
+		assert_eq!(index.query("", &[2, 1], 1, 2), vec![3, 5]);
+		assert_eq!(index.query("", &[2, 1], 3, 10), vec![6]);
+		assert!(index.query("", &[2, 1], 4, 10).is_empty());
+		assert!(index.query("", &[], 0, 0).is_empty());
+	}
+}
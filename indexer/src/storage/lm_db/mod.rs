@@ -1,20 +1,38 @@
 use std::fs;
+use std::sync::{Arc, RwLock};
 
-use heed::types::Str;
+use heed::byteorder::BigEndian;
+use heed::types::{OwnedType, SerdeJson, Str, U64};
 use heed::{Database, EnvOpenOptions};
+use tokio::sync::watch;
 
+use crate::storage::index::EventIndex;
 use crate::storage::lm_db::types::LMDBClientConfig;
 use crate::storage::types::BaseKVStorage;
+use crate::tasks::types::{EventKind, TaskResponse};
 
 pub mod types;
 
+type SeqId = OwnedType<U64<BigEndian>>;
+
 // todo change string to bytes?
 #[derive(Clone)]
 pub struct LMDBClient {
 	db: Database<Str, Str>,
+	// task records by sequence id, big endian to keep them ordered
+	events: Database<SeqId, SerdeJson<TaskResponse>>,
+	// schema id of the recorded attestations, by source address and attestation id
+	schemas: Database<Str, OwnedType<u64>>,
+	index: Arc<RwLock<EventIndex>>,
+	// last sequence id, to notify the subscribers of new records
+	updates: Arc<watch::Sender<u64>>,
 	env: heed::Env,
 }
 
+fn schema_key(source_address: &str, attestation_id: &str) -> String {
+	format!("{}:{}", source_address.to_lowercase(), attestation_id)
+}
+
 // https://github.com/meilisearch/heed/blob/main/heed/examples/all-types.rs
 impl LMDBClient {
 	pub fn new(config: LMDBClientConfig) -> Self {
@@ -27,7 +45,58 @@ impl LMDBClient {
 			.unwrap();
 
 		let db = env.create_database(Some(&config.db_name)).unwrap();
-		LMDBClient { db, env }
+		let events_name = format!("{}-events", config.db_name);
+		let events: Database<SeqId, SerdeJson<TaskResponse>> =
+			env.create_database(Some(&events_name)).unwrap();
+		let schemas_name = format!("{}-schemas", config.db_name);
+		let schemas = env.create_database(Some(&schemas_name)).unwrap();
+
+		// The index is kept in memory, rebuilt from the stored records
+		let mut index = EventIndex::default();
+		let mut last = 0;
+		{
+			let read_txn = env.read_txn().unwrap();
+			for entry in events.iter(&read_txn).unwrap() {
+				let (seq_id, record) = entry.unwrap();
+				index.insert(seq_id.get(), &record);
+				last = seq_id.get();
+			}
+		}
+		let (updates, _) = watch::channel(last);
+
+		LMDBClient {
+			db,
+			events,
+			schemas,
+			index: Arc::new(RwLock::new(index)),
+			updates: Arc::new(updates),
+			env,
+		}
+	}
+
+	/// Receiver of the last sequence id, changed whenever records are appended.
+	pub fn subscribe(&self) -> watch::Receiver<u64> {
+		self.updates.subscribe()
+	}
+
+	/// Records matching the filters, skipping the first `offset` of them,
+	/// along with their sequence ids.
+	pub fn get_events(
+		&self, source_address: &str, schema_ids: &[u32], offset: usize, count: usize,
+	) -> heed::Result<Vec<(u64, TaskResponse)>> {
+		let seq_ids: Vec<u64> = {
+			let index = self.index.read().expect("poisoned index lock");
+			index.query(source_address, schema_ids, offset, count)
+		};
+
+		let read_txn = self.env.read_txn()?;
+		let mut records = Vec::new();
+		for seq_id in seq_ids {
+			if let Some(record) = self.events.get(&read_txn, &U64::new(seq_id))? {
+				records.push((seq_id, record));
+			}
+		}
+		Ok(records)
 	}
 }
 
@@ -44,4 +113,158 @@ impl BaseKVStorage for LMDBClient {
 		let value = self.db.get(&read_txn, key).unwrap();
 		value.map(|v| v.to_string())
 	}
+
+	fn append(&self, key: &str, value: &str, records: &[TaskResponse]) -> heed::Result<()> {
+		// Held until the records are indexed, so that they are indexed in order
+		let mut index = self.index.write().expect("poisoned index lock");
+
+		let mut write_txn = self.env.write_txn()?;
+		let last = self.events.last(&write_txn)?.map_or(0, |(seq_id, _)| seq_id.get());
+		for (i, record) in records.iter().enumerate() {
+			let seq_id = last + 1 + i as u64;
+			self.events.put(&mut write_txn, &U64::new(seq_id), record)?;
+
+			// Only the attestation events are mapped to a schema
+			if record.attestation_id.is_empty() {
+				continue;
+			}
+			let key = schema_key(&record.source_address, &record.attestation_id);
+			match (record.kind, record.retraction) {
+				(EventKind::Recorded, _) => {
+					self.schemas.put(&mut write_txn, &key, &(record.schema_id as u64))?;
+				},
+				// The attestation was recorded in an orphaned block
+				(EventKind::Revoked, true) => {
+					self.schemas.delete(&mut write_txn, &key)?;
+				},
+				_ => {},
+			}
+		}
+		self.db.put(&mut write_txn, key, value)?;
+		write_txn.commit()?;
+
+		for (i, record) in records.iter().enumerate() {
+			index.insert(last + 1 + i as u64, record);
+		}
+		if !records.is_empty() {
+			self.updates.send_replace(last + records.len() as u64);
+		}
+		Ok(())
+	}
+
+	fn get_schema_id(&self, source_address: &str, attestation_id: &str) -> Option<usize> {
+		let read_txn = self.env.read_txn().unwrap();
+		let key = schema_key(source_address, attestation_id);
+		let schema_id = self.schemas.get(&read_txn, &key).unwrap();
+		schema_id.map(|x| x as usize)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::env;
+	use std::process;
+
+	use super::*;
+
+	fn config(test_name: &str) -> LMDBClientConfig {
+		let path = env::temp_dir().join(format!("indexer-lmdb-{}-{}", process::id(), test_name));
+		LMDBClientConfig {
+			db_name: "indexer".to_string(),
+			path: path.to_string_lossy().into_owned(),
+			max_dbs: 10,
+			map_size: 10 * 1024 * 1024,
+		}
+	}
+
+	fn client(config: &LMDBClientConfig) -> LMDBClient {
+		let _ = fs::remove_dir_all(&config.path);
+		LMDBClient::new(config.clone())
+	}
+
+	fn record(kind: EventKind, attestation_id: &str, retraction: bool) -> TaskResponse {
+		TaskResponse {
+			id: 0,
+			timestamp: "0".to_string(),
+			job_id: String::new(),
+			data: String::new(),
+			schema_id: 2,
+			source_address: "0xdead".to_string(),
+			kind,
+			attestation_id: attestation_id.to_string(),
+			retraction,
+		}
+	}
+
+	#[test]
+	fn should_map_recorded_attestations_to_their_schema() {
+		let config = config("schemas");
+		let db = client(&config);
+
+		let records = [
+			record(EventKind::Recorded, "0x01", false),
+			record(EventKind::Recorded, "0x02", false),
+			record(EventKind::Revoked, "0x01", false),
+		];
+		db.append("task", "{}", &records).unwrap();
+		// Revoked attestations keep their schema, for the retraction of the revocation
+		assert_eq!(db.get_schema_id("0xDEAD", "0x01"), Some(2));
+		assert_eq!(db.get_schema_id("0xdead", "0x02"), Some(2));
+		assert_eq!(db.get_schema_id("0xbeef", "0x02"), None);
+
+		// Attestations recorded in an orphaned block are forgotten
+		db.append("task", "{}", &[record(EventKind::Revoked, "0x02", true)]).unwrap();
+		assert_eq!(db.get_schema_id("0xdead", "0x02"), None);
+		assert_eq!(db.get_schema_id("0xdead", "0x01"), Some(2));
+
+		drop(db);
+		fs::remove_dir_all(config.path).unwrap();
+	}
+
+	#[test]
+	fn should_read_appended_events_after_reopening() {
+		let config = config("events");
+		let db = client(&config);
+
+		let event = |id, source_address: &str, schema_id| TaskResponse {
+			id,
+			source_address: source_address.to_string(),
+			schema_id,
+			..record(EventKind::Recorded, "", false)
+		};
+		let first = [event(10, "0xa", 0), event(11, "0xb", 1), event(12, "0xA", 1)];
+		db.append("task", "1", &first).unwrap();
+		db.append("task", "2", &[event(13, "0xa", 1), event(14, "0xb", 0)]).unwrap();
+		drop(db);
+
+		// The index and the last sequence id are rebuilt from the stored events
+		let db = LMDBClient::new(config.clone());
+		assert_eq!(db.get("task").as_deref(), Some("2"));
+		assert_eq!(*db.subscribe().borrow(), 5);
+
+		let ids = |events: Vec<(u64, TaskResponse)>| -> Vec<(u64, usize)> {
+			events.into_iter().map(|(seq_id, x)| (seq_id, x.id)).collect()
+		};
+		assert_eq!(
+			ids(db.get_events("", &[], 0, 10).unwrap()),
+			vec![(1, 10), (2, 11), (3, 12), (4, 13), (5, 14)]
+		);
+		assert_eq!(
+			ids(db.get_events("0xA", &[1], 0, 10).unwrap()),
+			vec![(3, 12), (4, 13)]
+		);
+		assert_eq!(ids(db.get_events("", &[1], 1, 1).unwrap()), vec![(3, 12)]);
+		assert!(db.get_events("0xc", &[], 0, 10).unwrap().is_empty());
+
+		// Appending resumes after the stored events
+		db.append("task", "3", &[event(15, "0xb", 1)]).unwrap();
+		assert_eq!(
+			ids(db.get_events("0xb", &[1], 0, 10).unwrap()),
+			vec![(2, 11), (6, 15)]
+		);
+		assert_eq!(*db.subscribe().borrow(), 6);
+
+		drop(db);
+		fs::remove_dir_all(config.path).unwrap();
+	}
 }
@@ -1,4 +1,5 @@
 use crate::storage::types::BaseKVStorage;
+use crate::tasks::types::TaskResponse;
 
 #[derive(Default)]
 pub struct MockDBClient;
@@ -18,4 +19,12 @@ impl BaseKVStorage for MockDBClient {
 	fn get(&self, _key: &str) -> Option<String> {
 		Some("Mock db response".to_string())
 	}
+
+	fn append(&self, _key: &str, _value: &str, _records: &[TaskResponse]) -> heed::Result<()> {
+		Ok(())
+	}
+
+	fn get_schema_id(&self, _source_address: &str, _attestation_id: &str) -> Option<usize> {
+		None
+	}
 }
@@ -1,3 +1,4 @@
+pub mod index;
 pub mod lm_db;
 pub mod mock_db;
 pub mod types;
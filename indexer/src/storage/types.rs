@@ -1,9 +1,18 @@
 // todo generic Result
 use heed::Result;
 
+use crate::tasks::types::TaskResponse;
+
 // #[tonic::async_trait]
-pub trait BaseKVStorage {
+pub trait BaseKVStorage: Send + Sync {
 	fn put(&self, key: &str, value: &str) -> Result<()>;
 
 	fn get(&self, key: &str) -> Option<String>;
+
+	// store records under the next sequence ids, along with the state of the task
+	// that produced them, in a single transaction
+	fn append(&self, key: &str, value: &str, records: &[TaskResponse]) -> Result<()>;
+
+	// schema id of an attestation recorded by the source, as appended
+	fn get_schema_id(&self, source_address: &str, attestation_id: &str) -> Option<usize>;
 }
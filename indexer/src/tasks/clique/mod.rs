@@ -1 +1,2 @@
+pub mod reorg;
 pub mod task;
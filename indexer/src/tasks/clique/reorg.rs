@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+
+use digest::Digest;
+use serde::{Deserialize, Serialize};
+use sha3::Sha3_256;
+use tracing::warn;
+
+use crate::tasks::types::{EventKind, TaskResponse};
+
+// indexed blocks kept to find the fork point of a reorg
+const MAX_RECENT_BLOCKS: usize = 128;
+
+/// Indexed block, with the records emitted for it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexedBlock {
+	pub number: u64,
+	// 0x-hex
+	pub hash: String,
+	pub records: Vec<TaskResponse>,
+}
+
+/// Latest indexed blocks, in ascending order.
+/// Only the last block of each polled range and the blocks holding events are kept,
+/// which is enough to find a block before the fork, if not the latest one.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RecentBlocks {
+	blocks: VecDeque<IndexedBlock>,
+	// latest block checked against the chain, along with the kept ones before it
+	#[serde(default)]
+	verified: u64,
+}
+
+impl RecentBlocks {
+	pub fn verified(&self) -> u64 {
+		self.verified
+	}
+
+	/// Mark the blocks up to `number` as checked against the chain.
+	pub fn verify(&mut self, number: u64) {
+		self.verified = number;
+	}
+
+	/// Blocks from the latest to the oldest.
+	pub fn iter_rev(&self) -> impl Iterator<Item = &IndexedBlock> {
+		self.blocks.iter().rev()
+	}
+
+	pub fn push(&mut self, block: IndexedBlock) {
+		match self.blocks.back_mut() {
+			Some(last) if last.number == block.number => last.records.extend(block.records),
+			_ => self.blocks.push_back(block),
+		}
+		while self.blocks.len() > MAX_RECENT_BLOCKS {
+			self.blocks.pop_front();
+		}
+	}
+
+	/// Drop the blocks past `number`, returning the events that undo their records.
+	/// The kept blocks are the ones still on the chain.
+	pub fn roll_back(&mut self, number: u64) -> Vec<TaskResponse> {
+		self.verified = number;
+		let split = self.blocks.iter().position(|x| x.number > number).unwrap_or(self.blocks.len());
+		let orphaned: Vec<TaskResponse> =
+			self.blocks.split_off(split).into_iter().flat_map(|x| x.records).collect();
+		let kept: Vec<&TaskResponse> = self.blocks.iter().flat_map(|x| &x.records).collect();
+		retractions(&kept, &orphaned)
+	}
+}
+
+/// Events undoing the orphaned records, latest first.
+/// A recorded attestation is revoked. An update, or a revocation, is undone
+/// with the previous data of the attestation, when it is among the kept blocks.
+fn retractions(kept: &[&TaskResponse], orphaned: &[TaskResponse]) -> Vec<TaskResponse> {
+	let mut retractions = Vec::new();
+	for (i, record) in orphaned.iter().enumerate().rev() {
+		let history = || {
+			kept.iter()
+				.copied()
+				.chain(&orphaned[..i])
+				.filter(|x| x.attestation_id == record.attestation_id)
+		};
+		let previous = history().last();
+		let recorded = history().find(|x| x.kind == EventKind::Recorded);
+
+		let retraction = match (record.kind, previous, recorded) {
+			(EventKind::Recorded, ..) => {
+				TaskResponse { kind: EventKind::Revoked, data: String::new(), ..record.clone() }
+			},
+			(EventKind::Updated, Some(previous), _) if previous.kind != EventKind::Revoked => {
+				TaskResponse { data: previous.data.clone(), ..record.clone() }
+			},
+			(EventKind::Revoked, Some(previous), Some(recorded))
+				if previous.kind != EventKind::Revoked =>
+			{
+				TaskResponse {
+					kind: EventKind::Recorded,
+					schema_id: recorded.schema_id,
+					data: previous.data.clone(),
+					..record.clone()
+				}
+			},
+			_ => {
+				warn!(
+					"Can't undo {:?} of attestation {}, its previous data is unknown",
+					record.kind, record.attestation_id
+				);
+				continue;
+			},
+		};
+		retractions.push(TaskResponse {
+			id: retraction_id(record.id),
+			retraction: true,
+			..retraction
+		});
+	}
+	retractions
+}
+
+// deterministic, and distinct from the id of the retracted record
+fn retraction_id(id: usize) -> usize {
+	let mut hasher = Sha3_256::new();
+	hasher.update((id as u64).to_be_bytes());
+	hasher.update(b"retraction");
+	let hash = hasher.finalize();
+	let bytes: [u8; 8] = hash[..8].try_into().expect("hash is longer than 8 bytes");
+	u64::from_be_bytes(bytes) as usize
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn record(id: usize, kind: EventKind, attestation_id: &str, data: &str) -> TaskResponse {
+		TaskResponse {
+			id,
+			timestamp: "1000".to_string(),
+			job_id: "clique:test".to_string(),
+			data: data.to_string(),
+			schema_id: 2,
+			source_address: "0xdead".to_string(),
+			kind,
+			attestation_id: attestation_id.to_string(),
+			retraction: false,
+		}
+	}
+
+	fn recent_blocks(blocks: Vec<(u64, Vec<TaskResponse>)>) -> RecentBlocks {
+		let mut recent_blocks = RecentBlocks::default();
+		for (number, records) in blocks {
+			recent_blocks.push(IndexedBlock { number, hash: format!("0x{:x}", number), records });
+		}
+		recent_blocks
+	}
+
+	#[test]
+	fn should_revoke_orphaned_recorded_attestations() {
+		let mut blocks = recent_blocks(vec![
+			(1, vec![record(1, EventKind::Recorded, "0x01", "trust")]),
+			(2, vec![record(2, EventKind::Recorded, "0x02", "distrust")]),
+		]);
+
+		let retractions = blocks.roll_back(1);
+		assert_eq!(retractions.len(), 1);
+		let retraction = &retractions[0];
+		assert_eq!(
+			(retraction.kind, retraction.retraction),
+			(EventKind::Revoked, true)
+		);
+		assert_eq!(retraction.attestation_id, "0x02");
+		assert_eq!(retraction.schema_id, 2);
+		assert!(retraction.data.is_empty());
+		assert_ne!(retraction.id, 2);
+
+		// The kept blocks are verified, the orphaned ones are gone
+		assert_eq!(blocks.verified(), 1);
+		assert_eq!(
+			blocks.iter_rev().map(|x| x.number).collect::<Vec<_>>(),
+			vec![1]
+		);
+	}
+
+	#[test]
+	fn should_undo_orphaned_updates_with_previous_data() {
+		let mut blocks = recent_blocks(vec![
+			(1, vec![record(1, EventKind::Recorded, "0x01", "trust")]),
+			(2, vec![record(2, EventKind::Updated, "0x01", "distrust")]),
+			(3, vec![record(3, EventKind::Updated, "0x01", "neutral")]),
+		]);
+
+		// Undone latest first, each back to the data before it
+		let retractions = blocks.roll_back(1);
+		let undone: Vec<(EventKind, &str)> =
+			retractions.iter().map(|x| (x.kind, x.data.as_str())).collect();
+		assert_eq!(
+			undone,
+			vec![(EventKind::Updated, "distrust"), (EventKind::Updated, "trust")]
+		);
+		assert!(retractions.iter().all(|x| x.retraction));
+	}
+
+	#[test]
+	fn should_record_again_orphaned_revocations() {
+		let mut recorded = record(1, EventKind::Recorded, "0x01", "trust");
+		recorded.schema_id = 1;
+		let mut blocks = recent_blocks(vec![
+			(
+				1,
+				vec![recorded, record(2, EventKind::Updated, "0x01", "distrust")],
+			),
+			(2, vec![record(3, EventKind::Revoked, "0x01", "")]),
+		]);
+
+		let retractions = blocks.roll_back(1);
+		assert_eq!(retractions.len(), 1);
+		let retraction = &retractions[0];
+		assert_eq!(retraction.kind, EventKind::Recorded);
+		// With the latest data, under the schema of the recorded attestation
+		assert_eq!(
+			(retraction.data.as_str(), retraction.schema_id),
+			("distrust", 1)
+		);
+		assert!(retraction.retraction);
+	}
+
+	#[test]
+	fn should_skip_undoing_events_of_unknown_history() {
+		// The attestation was recorded before the kept blocks
+		let mut blocks = recent_blocks(vec![
+			(1, Vec::new()),
+			(2, vec![record(2, EventKind::Updated, "0x01", "distrust")]),
+			(3, vec![record(3, EventKind::Revoked, "0x01", "")]),
+		]);
+
+		assert!(blocks.roll_back(1).is_empty());
+		assert_eq!(blocks.verified(), 1);
+	}
+}
@@ -1,22 +1,48 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::error::Error;
 use std::time::Duration;
 
 use digest::Digest;
+use ethers::core::types::H256;
 use hex;
 use serde::{Deserialize, Serialize};
 use serde_json;
 use sha3::Sha3_256;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 
 // todo change to EVMLogsClient, make threadsafe
-use crate::clients::clique::client::CliqueClient;
+use crate::clients::clique::client::{CliqueClient, CliqueEvent, CliqueEventLog};
 use crate::clients::clique::types::EVMIndexerConfig;
-use crate::tasks::types::{BaseTask, BaseTaskState, TaskResponse};
+use crate::storage::types::BaseKVStorage;
+use crate::tasks::clique::reorg::{IndexedBlock, RecentBlocks};
+use crate::tasks::types::{BaseTask, BaseTaskState, EventKind, TaskResponse};
+
+// polling interval, once the latest block is reached
+const SYNCED_SLEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+fn to_hex(hash: H256) -> String {
+	format!("0x{}", hex::encode(hash))
+}
+
+/// Deterministic id of an event, from its position on-chain.
+fn log_id(log: &CliqueEventLog) -> usize {
+	let mut hasher = Sha3_256::new();
+	hasher.update(log.tx_hash.as_bytes());
+	hasher.update(log.log_index.to_be_bytes());
+	hasher.update((log.batch_index as u64).to_be_bytes());
+	let hash = hasher.finalize();
+	let bytes: [u8; 8] = hash[..8].try_into().expect("hash is longer than 8 bytes");
+	u64::from_be_bytes(bytes) as usize
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CliqueTaskState {
 	from_block: u64,
 	range: u64,
 	global: BaseTaskState,
+	#[serde(default)]
+	recent_blocks: RecentBlocks,
 }
 
 pub struct CliqueTask {
@@ -24,25 +50,149 @@ pub struct CliqueTask {
 	// todo tmp, remove pub
 	pub client: CliqueClient,
 	state: CliqueTaskState,
+	// schema id of the indexed attestations, kept along with the records
+	db: Box<dyn BaseKVStorage>,
+	// schema id of the attestations recorded in the current batch, not stored yet
+	pending_schema_ids: HashMap<String, usize>,
 }
 
 impl CliqueTask {
-	pub fn new(config: EVMIndexerConfig, client: CliqueClient) -> Self {
-		// todo restore prev state
+	pub fn new(config: EVMIndexerConfig, client: CliqueClient, db: Box<dyn BaseKVStorage>) -> Self {
 		let from_block = config.from_block;
 		let range = 100;
 
 		let global = BaseTaskState { is_synced: false, is_finished: false, records_total: 0 };
 
-		let state = CliqueTaskState { from_block, range, global };
+		let state =
+			CliqueTaskState { from_block, range, global, recent_blocks: RecentBlocks::default() };
 
 		debug!("Clique task created");
-		CliqueTask { config, client, state }
+		CliqueTask { config, client, state, db, pending_schema_ids: HashMap::new() }
 	}
 
 	fn update_state(&mut self, new_state: CliqueTaskState) {
 		self.state = new_state;
 	}
+
+	async fn is_canonical(&self, number: u64, hash: &str) -> Result<bool, Box<dyn Error>> {
+		let canonical_hash = self.client.block_hash(number).await?;
+		Ok(canonical_hash.map(to_hex).as_deref() == Some(hash))
+	}
+
+	/// Compare the blocks indexed since the last check with the chain. If any was dropped,
+	/// roll back to the latest kept block still on the chain, returning the retraction events.
+	/// Each of them is checked, since their hashes weren't all read at once.
+	async fn check_reorg(&mut self) -> Result<Option<Vec<TaskResponse>>, Box<dyn Error>> {
+		let blocks: Vec<(u64, String)> =
+			self.state.recent_blocks.iter_rev().map(|x| (x.number, x.hash.clone())).collect();
+		let verified = self.state.recent_blocks.verified();
+
+		// The latest verified block stands for the ones before it
+		let mut dropped = None;
+		let mut probed = 0;
+		for (i, (number, hash)) in blocks.iter().enumerate() {
+			probed = i + 1;
+			if !self.is_canonical(*number, hash).await? {
+				dropped = Some(i);
+			}
+			if *number <= verified {
+				break;
+			}
+		}
+		let Some(dropped) = dropped else {
+			if let Some((number, _)) = blocks.first() {
+				self.state.recent_blocks.verify(*number);
+			}
+			return Ok(None);
+		};
+
+		// The fork is below the oldest dropped block
+		let mut fork = None;
+		for (i, (number, hash)) in blocks.iter().enumerate().skip(dropped + 1) {
+			if i < probed || self.is_canonical(*number, hash).await? {
+				fork = Some(*number);
+				break;
+			}
+		}
+		// Every kept block was dropped, they are all indexed again
+		let number = fork.unwrap_or_else(|| {
+			let oldest = blocks.last().map_or(0, |(number, _)| *number);
+			error!(
+				"Reorg deeper than the kept blocks, indexing again from block {}",
+				oldest
+			);
+			oldest.saturating_sub(1)
+		});
+
+		let retractions = self.state.recent_blocks.roll_back(number);
+		warn!(
+			"Reorg past block {}, retracting {} records",
+			number,
+			retractions.len()
+		);
+		self.state.from_block = number + 1;
+		self.state.global.is_synced = false;
+		self.state.global.records_total += retractions.len();
+		Ok(Some(retractions))
+	}
+
+	/// Map a registry event to a task response, skipping attestations of unknown schemas.
+	/// Updates and revocations carry no schema, it is the one of the recorded attestation,
+	/// so they are skipped as well if it wasn't indexed.
+	fn parse_log(&mut self, log: &CliqueEventLog) -> Option<TaskResponse> {
+		let (kind, attestation_id, data) = match &log.event {
+			CliqueEvent::Recorded(attestation) => (
+				EventKind::Recorded,
+				attestation.attestation_id,
+				attestation.attestation_data.first(),
+			),
+			CliqueEvent::Updated(update) => (
+				EventKind::Updated,
+				update.attestation_id,
+				update.attestation_data.first(),
+			),
+			CliqueEvent::Revoked(attestation_id) => (EventKind::Revoked, *attestation_id, None),
+		};
+		let attestation_id = format!("0x{}", hex::encode(attestation_id));
+		let source_address = self.config.master_registry_contract.to_lowercase();
+		let schema_id = match &log.event {
+			CliqueEvent::Recorded(attestation) => {
+				let registry_schema_id = format!("0x{}", hex::encode(attestation.schema_id));
+				let Some(schema_id) = self.config.schema_ids.get(&registry_schema_id) else {
+					warn!("Skipping attestation of unknown schema {}", registry_schema_id);
+					return None;
+				};
+				self.pending_schema_ids.insert(attestation_id.clone(), *schema_id);
+				*schema_id
+			},
+			_ => {
+				let schema_id = self
+					.pending_schema_ids
+					.get(&attestation_id)
+					.copied()
+					.or_else(|| self.db.get_schema_id(&source_address, &attestation_id));
+				let Some(schema_id) = schema_id else {
+					debug!("Skipping {:?} of unindexed attestation {}", kind, attestation_id);
+					return None;
+				};
+				schema_id
+			},
+		};
+		let data = data.map_or(String::new(), |x| String::from_utf8_lossy(x).into_owned());
+
+		Some(TaskResponse {
+			id: log_id(log),
+			// milliseconds, like the rest of the task responses
+			timestamp: (log.timestamp * 1000).to_string(),
+			job_id: self.get_id(),
+			data,
+			schema_id,
+			source_address,
+			kind,
+			attestation_id,
+			retraction: false,
+		})
+	}
 }
 
 #[tonic::async_trait]
@@ -54,33 +204,75 @@ impl BaseTask for CliqueTask {
 			self.state.from_block + self.state.range - 1
 		);
 
-		// todo
-		let _ = self.client.query(Some(self.state.from_block), Some(self.state.range)).await;
+		// The attestations of the previous batch are stored by now
+		self.pending_schema_ids.clear();
 
-		let logs = vec![String::from("Hello")];
-
-		if !logs.is_empty() {
-			info!("Found {:?} log records", logs.len());
+		// Undo the blocks dropped by a reorg, before indexing past them
+		match self.check_reorg().await {
+			Ok(Some(retractions)) => return retractions,
+			Ok(None) => {},
+			Err(e) => {
+				error!("Failed to check for a reorg: {}", e);
+				return Vec::new();
+			},
 		}
 
-		// todo set to actual last synced block
-		let from_block_new = self.state.from_block + self.state.range;
-
-		let new_state = CliqueTaskState {
-			from_block: from_block_new,
-			global: self.state.global.clone(),
-			..self.state
+		// Keep the cursor on failure, so that the range is retried
+		let result =
+			match self.client.query(Some(self.state.from_block), Some(self.state.range)).await {
+				Ok(result) => result,
+				Err(e) => {
+					error!("Failed to query registry events: {}", e);
+					return Vec::new();
+				},
+			};
+		// The hash of the last block is kept to detect reorgs, on the next poll
+		let to_block_hash = if result.to_block < self.state.from_block {
+			None
+		} else {
+			match self.client.block_hash(result.to_block).await {
+				Ok(hash) => hash,
+				Err(e) => {
+					error!("Failed to query block {}: {}", result.to_block, e);
+					return Vec::new();
+				},
+			}
 		};
 
-		self.update_state(new_state);
+		let mut results = Vec::new();
+		for log in &result.logs {
+			let Some(record) = self.parse_log(log) else {
+				continue;
+			};
+			results.push(record.clone());
+			self.state.recent_blocks.push(IndexedBlock {
+				number: log.block_number,
+				hash: to_hex(log.block_hash),
+				records: vec![record],
+			});
+		}
+		if let Some(hash) = to_block_hash {
+			let block =
+				IndexedBlock { number: result.to_block, hash: to_hex(hash), records: Vec::new() };
+			self.state.recent_blocks.push(block);
+		}
+		if !results.is_empty() {
+			info!("Found {:?} registry events", results.len());
+		}
+
+		self.state.from_block = cmp::max(self.state.from_block, result.to_block + 1);
+		self.state.global.is_synced = result.to_block >= result.confirmed_block;
+		self.state.global.records_total += results.len();
 
-		let res: Vec<TaskResponse> = Vec::new();
-		res
+		results
 	}
 
 	fn get_sleep_interval(&self) -> Duration {
-		// todo interval if reaches the latest onchain block
-		Duration::from_secs(0)
+		if self.state.global.is_synced {
+			SYNCED_SLEEP_INTERVAL
+		} else {
+			Duration::from_secs(0)
+		}
 	}
 
 	// todo use chain id instead of rpc url
@@ -109,4 +301,105 @@ impl BaseTask for CliqueTask {
 	fn get_state_dump(&self) -> String {
 		serde_json::to_string(&self.state).expect("Failed to serialize to JSON")
 	}
+
+	fn restore_state(&mut self, dump: &str) -> serde_json::Result<()> {
+		let state: CliqueTaskState = serde_json::from_str(dump)?;
+		self.update_state(state);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use ethers::core::types::Bytes;
+
+	use super::*;
+	use crate::clients::clique::client::{Attestation, UpdateRequest};
+	use crate::storage::mock_db::MockDBClient;
+
+	const SCHEMA: [u8; 32] = [1; 32];
+
+	fn task() -> CliqueTask {
+		let config = EVMIndexerConfig {
+			rpc_url: "http://localhost:8545".to_string(),
+			master_registry_contract: "0x000000000000000000000000000000000000dEaD".to_string(),
+			from_block: 0,
+			confirmations: 0,
+			schema_ids: HashMap::from([(format!("0x{}", hex::encode(SCHEMA)), 2)]),
+		};
+		CliqueTask::new(
+			config.clone(),
+			CliqueClient::new(config),
+			Box::new(MockDBClient::new()),
+		)
+	}
+
+	fn log(event: CliqueEvent, log_index: u64, batch_index: usize) -> CliqueEventLog {
+		CliqueEventLog {
+			event,
+			block_number: 1,
+			block_hash: H256::zero(),
+			timestamp: 10,
+			tx_hash: H256::repeat_byte(1),
+			log_index,
+			batch_index,
+		}
+	}
+
+	fn recorded(attestation_id: [u8; 32], schema_id: [u8; 32]) -> CliqueEvent {
+		CliqueEvent::Recorded(Attestation {
+			attestation_id,
+			schema_id,
+			attestation_data: vec![Bytes::from(b"trust".to_vec())],
+			..Attestation::default()
+		})
+	}
+
+	#[test]
+	fn should_derive_distinct_log_ids() {
+		let event = CliqueEvent::Revoked([0; 32]);
+		assert_eq!(
+			log_id(&log(event.clone(), 0, 0)),
+			log_id(&log(event.clone(), 0, 0))
+		);
+		assert_ne!(
+			log_id(&log(event.clone(), 0, 0)),
+			log_id(&log(event.clone(), 1, 0))
+		);
+		assert_ne!(log_id(&log(event.clone(), 0, 0)), log_id(&log(event, 0, 1)));
+	}
+
+	#[test]
+	fn should_map_events_to_the_schema_of_the_attestation() {
+		let mut task = task();
+
+		let record = task.parse_log(&log(recorded([7; 32], SCHEMA), 0, 0)).unwrap();
+		assert_eq!(record.kind, EventKind::Recorded);
+		assert_eq!(record.schema_id, 2);
+		assert_eq!(record.data, "trust");
+		assert_eq!(record.timestamp, "10000");
+		assert_eq!(
+			record.source_address,
+			"0x000000000000000000000000000000000000dead"
+		);
+		assert_eq!(record.attestation_id, format!("0x{}", hex::encode([7; 32])));
+
+		// Updates and revocations carry the schema of the recorded attestation
+		let update = CliqueEvent::Updated(UpdateRequest {
+			attestation_id: [7; 32],
+			attestation_data: vec![Bytes::from(b"distrust".to_vec())],
+			..UpdateRequest::default()
+		});
+		let record = task.parse_log(&log(update, 1, 0)).unwrap();
+		assert_eq!((record.kind, record.schema_id), (EventKind::Updated, 2));
+		assert_eq!(record.data, "distrust");
+
+		let record = task.parse_log(&log(CliqueEvent::Revoked([7; 32]), 2, 0)).unwrap();
+		assert_eq!((record.kind, record.schema_id), (EventKind::Revoked, 2));
+		assert!(record.data.is_empty());
+
+		// Attestations of unknown schemas are skipped, along with their revocations
+		assert!(task.parse_log(&log(recorded([8; 32], [2; 32]), 3, 0)).is_none());
+		assert!(task.parse_log(&log(CliqueEvent::Revoked([8; 32]), 4, 0)).is_none());
+	}
 }
@@ -8,7 +8,7 @@ use sha3::Sha3_256;
 use tracing::{debug, info};
 
 use crate::clients::csv::client::CSVClient;
-use crate::tasks::types::{BaseTask, BaseTaskState, TaskResponse};
+use crate::tasks::types::{BaseTask, BaseTaskState, EventKind, TaskResponse};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CSVPOCTaskState {
@@ -19,6 +19,7 @@ pub struct CSVPOCTaskState {
 
 pub struct CSVPOCTask {
 	client: CSVClient,
+	source_address: String,
 	state: CSVPOCTaskState,
 }
 
@@ -28,14 +29,13 @@ const CSV_COLUMN_INDEX_TIMESTAMP: usize = 1;
 const CSV_COLUMN_INDEX: usize = 0;
 
 impl CSVPOCTask {
-	pub fn new(client: CSVClient) -> Self {
+	pub fn new(client: CSVClient, source_address: String) -> Self {
 		let global = BaseTaskState { is_synced: false, is_finished: false, records_total: 0 };
 
-		// todo restore prev state
 		let state = CSVPOCTaskState { from: 0, range: 2000, global };
 
 		debug!("CSV POC task created");
-		CSVPOCTask { client, state }
+		CSVPOCTask { client, source_address, state }
 	}
 
 	fn update_state(&mut self, new_state: CSVPOCTaskState) {
@@ -47,9 +47,9 @@ impl CSVPOCTask {
 impl BaseTask for CSVPOCTask {
 	async fn run(&mut self, offset: Option<u64>, limit: Option<u64>) -> Vec<TaskResponse> {
 		let from = offset.unwrap_or(self.state.from);
-		let range = limit.unwrap_or(self.state.from + self.state.range);
+		let range = limit.unwrap_or(self.state.range);
 
-		info!("Parsing CSV [{}..{}] lines", from, range);
+		info!("Parsing CSV [{}..{}] lines", from, from + range);
 
 		let records = self.client.query(Some(from), Some(range)).await.unwrap();
 
@@ -58,6 +58,7 @@ impl BaseTask for CSVPOCTask {
 
 		let is_finished = self.state.range > records_total.try_into().unwrap();
 
+		let job_id = self.get_id();
 		let results: Vec<TaskResponse> = records
 			.into_iter()
 			.map(|record| -> TaskResponse {
@@ -67,21 +68,25 @@ impl BaseTask for CSVPOCTask {
 				TaskResponse {
 					timestamp: r.get(CSV_COLUMN_INDEX_TIMESTAMP).unwrap().to_string(),
 					id: r.get(CSV_COLUMN_INDEX).unwrap().parse::<usize>().unwrap_or(0),
-					job_id: "0".to_string(),
+					job_id: job_id.clone(),
 					schema_id,
+					source_address: self.source_address.clone(),
 					data: r.get(CSV_COLUMN_INDEX_DATA).unwrap().to_string(),
+					kind: EventKind::Recorded,
+					attestation_id: String::new(),
+					retraction: false,
 				}
 			})
 			.collect();
 
 		let global = BaseTaskState { is_synced: is_finished, is_finished, records_total };
 
-		let _from_new = self.state.from + self.state.range;
-		let new_state = CSVPOCTaskState {
-			// from: _from_new,
-			global,
-			..self.state
+		// Only the indexing run moves the cursor, chunks requested by offset don't
+		let from_new = match offset {
+			Some(_) => self.state.from,
+			None => from + records_total as u64,
 		};
+		let new_state = CSVPOCTaskState { from: from_new, global, ..self.state };
 
 		self.update_state(new_state);
 
@@ -93,8 +98,7 @@ impl BaseTask for CSVPOCTask {
 	}
 
 	fn get_id(&self) -> String {
-		// todo filename
-		let data = "file".to_string();
+		let data = self.client.get_path().to_string();
 		let mut hasher = Sha3_256::new();
 		hasher.update(data.as_bytes());
 		let byte_vector = hasher.finalize().to_vec();
@@ -114,4 +118,65 @@ impl BaseTask for CSVPOCTask {
 	fn get_state_dump(&self) -> String {
 		serde_json::to_string(&self.state).expect("Failed to serialize to JSON")
 	}
+
+	fn restore_state(&mut self, dump: &str) -> serde_json::Result<()> {
+		let state: CSVPOCTaskState = serde_json::from_str(dump)?;
+		self.update_state(state);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::env;
+	use std::fs;
+	use std::process;
+
+	use super::*;
+	use crate::clients::csv::types::CSVClientConfig;
+
+	fn csv_path(test_name: &str) -> String {
+		let path = env::temp_dir().join(format!("indexer-csv-{}-{}.csv", process::id(), test_name));
+		path.to_string_lossy().into_owned()
+	}
+
+	fn write_lines(path: &str, ids: impl Iterator<Item = usize>) {
+		let mut csv = "id;timestamp;schema_id;schema_value\n".to_string();
+		ids.for_each(|id| csv.push_str(&format!("{};{};2;trust\n", id, id * 1000)));
+		fs::write(path, csv).unwrap();
+	}
+
+	fn task(path: &str) -> CSVPOCTask {
+		let client = CSVClient::new(CSVClientConfig { path: path.to_string() });
+		CSVPOCTask::new(client, "0xa".to_string())
+	}
+
+	#[tokio::test]
+	async fn should_resume_from_restored_state() {
+		let path = csv_path("resume");
+		write_lines(&path, 1..=3);
+
+		let mut first = task(&path);
+		let records = first.run(None, None).await;
+		assert_eq!(
+			records.iter().map(|x| x.id).collect::<Vec<_>>(),
+			vec![1, 2, 3]
+		);
+		assert!(first.get_is_finished());
+		let dump = first.get_state_dump();
+
+		// A restarted task picks up the lines appended since
+		let mut second = task(&path);
+		assert_eq!(second.get_id(), first.get_id());
+		second.restore_state(&dump).unwrap();
+		assert_eq!(second.get_state_dump(), dump);
+		write_lines(&path, 1..=5);
+		let records = second.run(None, None).await;
+		assert_eq!(records.iter().map(|x| x.id).collect::<Vec<_>>(), vec![4, 5]);
+		assert_eq!(records[0].timestamp, "4000");
+		assert_eq!(records[0].schema_id, 2);
+
+		assert!(second.restore_state("{}").is_err());
+		fs::remove_file(path).unwrap();
+	}
 }
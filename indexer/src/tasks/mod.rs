@@ -1,4 +1,5 @@
 pub mod clique;
 pub mod csv_poc;
 pub mod service;
+pub mod supervisor;
 pub mod types;
@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use tracing::info;
+use tracing::{error, info, warn};
 
 use crate::storage::types::BaseKVStorage;
 use crate::tasks::types::{BaseTask, TaskResponse};
@@ -12,15 +12,23 @@ pub struct TaskService {
 
 // todo global generic state
 impl TaskService {
-	pub fn new(task: Box<dyn BaseTask>, db: Box<dyn BaseKVStorage>) -> Self {
+	pub fn new(mut task: Box<dyn BaseTask>, db: Box<dyn BaseKVStorage>) -> Self {
 		let task_id = task.get_id();
-		// todo pass to a task
-		let restored_state = db.get(task_id.as_str()).unwrap_or("{}".to_string());
-		info!("Job created id={}, state={}", task_id, restored_state);
+		match db.get(task_id.as_str()) {
+			Some(state) => match task.restore_state(&state) {
+				Ok(()) => info!("Job restored id={}, state={}", task_id, state),
+				Err(e) => warn!("Job id={} starts over, invalid state: {}", task_id, e),
+			},
+			None => info!("Job created id={}", task_id),
+		}
 
 		TaskService { task, db }
 	}
 
+	pub fn get_id(&self) -> String {
+		self.task.get_id()
+	}
+
 	pub async fn run(&mut self) {
 		self.index().await;
 	}
@@ -29,11 +37,17 @@ impl TaskService {
 		// todo catch inner level errors
 		loop {
 			let n: Option<u64> = None;
-			self.task.run(n, n).await;
+			let mut records = self.task.run(n, n).await;
 
+			// The records are stored along with the state they lead to,
+			// so that a restart neither skips nor repeats any of them
 			let task_id = self.task.get_id();
+			records.iter_mut().for_each(|x| x.job_id = task_id.clone());
 			let task_state = self.task.get_state_dump();
-			let _ = self.db.put(task_id.as_str(), task_state.as_str());
+			if let Err(e) = self.db.append(task_id.as_str(), task_state.as_str(), &records) {
+				error!("Job id={} failed to store records: {}", task_id, e);
+				self.rollback(&task_id);
+			}
 
 			let state = self.task.get_state();
 
@@ -48,6 +62,16 @@ impl TaskService {
 		}
 	}
 
+	// rewind the task to its last stored state, so that the lost records are produced again
+	fn rollback(&mut self, task_id: &str) {
+		let Some(state) = self.db.get(task_id) else {
+			return;
+		};
+		if let Err(e) = self.task.restore_state(&state) {
+			warn!("Job id={} failed to roll back: {}", task_id, e);
+		}
+	}
+
 	pub async fn sleep(&self, duration: Duration) {
 		tokio::time::sleep(duration).await;
 	}
@@ -56,9 +80,123 @@ impl TaskService {
 		println!("{:?}", data);
 		data
 	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::env;
+	use std::fs;
+	use std::io;
+	use std::process;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::{Arc, Mutex};
+
+	use super::*;
+	use crate::clients::csv::client::CSVClient;
+	use crate::clients::csv::types::CSVClientConfig;
+	use crate::storage::lm_db::types::LMDBClientConfig;
+	use crate::storage::lm_db::LMDBClient;
+	use crate::tasks::csv_poc::task::CSVPOCTask;
+
+	fn temp_path(test_name: &str) -> String {
+		let path = env::temp_dir().join(format!("indexer-service-{}-{}", process::id(), test_name));
+		path.to_string_lossy().into_owned()
+	}
+
+	fn write_lines(path: &str, ids: impl Iterator<Item = usize>) {
+		let mut csv = "id;timestamp;schema_id;schema_value\n".to_string();
+		ids.for_each(|id| csv.push_str(&format!("{};{};2;trust\n", id, id * 1000)));
+		fs::write(path, csv).unwrap();
+	}
+
+	fn task(path: &str) -> Box<CSVPOCTask> {
+		let client = CSVClient::new(CSVClientConfig { path: path.to_string() });
+		Box::new(CSVPOCTask::new(client, "0xa".to_string()))
+	}
+
+	/// Storage failing the first append.
+	#[derive(Clone, Default)]
+	struct FlakyDB {
+		failed: Arc<AtomicBool>,
+		state: Arc<Mutex<Option<String>>>,
+		records: Arc<Mutex<Vec<TaskResponse>>>,
+	}
+
+	impl BaseKVStorage for FlakyDB {
+		fn put(&self, _key: &str, value: &str) -> heed::Result<()> {
+			*self.state.lock().unwrap() = Some(value.to_string());
+			Ok(())
+		}
+
+		fn get(&self, _key: &str) -> Option<String> {
+			self.state.lock().unwrap().clone()
+		}
+
+		fn append(&self, key: &str, value: &str, records: &[TaskResponse]) -> heed::Result<()> {
+			if !self.failed.swap(true, Ordering::SeqCst) {
+				return Err(heed::Error::Io(io::Error::new(
+					io::ErrorKind::Other,
+					"full",
+				)));
+			}
+			self.records.lock().unwrap().extend_from_slice(records);
+			self.put(key, value)
+		}
+
+		fn get_schema_id(&self, _source_address: &str, _attestation_id: &str) -> Option<usize> {
+			None
+		}
+	}
+
+	#[tokio::test]
+	async fn should_resume_restarted_task_without_repeating_records() {
+		let csv_path = format!("{}.csv", temp_path("resume"));
+		write_lines(&csv_path, 1..=3);
+		let config = LMDBClientConfig {
+			db_name: "indexer".to_string(),
+			path: temp_path("resume-lmdb"),
+			max_dbs: 10,
+			map_size: 10 * 1024 * 1024,
+		};
+		let _ = fs::remove_dir_all(&config.path);
+		let db = LMDBClient::new(config.clone());
+
+		let mut service = TaskService::new(task(&csv_path), Box::new(db.clone()));
+		service.run().await;
+		let task_id = service.get_id();
+		let dump = db.get(&task_id).unwrap();
+
+		// The restarted task is restored from the stored state, and only reads the new lines
+		write_lines(&csv_path, 1..=5);
+		let mut service = TaskService::new(task(&csv_path), Box::new(db.clone()));
+		assert_eq!(service.task.get_state_dump(), dump);
+		service.run().await;
+
+		let records = db.get_events("", &[], 0, 10).unwrap();
+		let ids: Vec<usize> = records.iter().map(|(_, x)| x.id).collect();
+		assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+		assert!(records.iter().all(|(_, x)| x.job_id == task_id));
+
+		drop(db);
+		fs::remove_dir_all(config.path).unwrap();
+		fs::remove_file(csv_path).unwrap();
+	}
+
+	#[tokio::test]
+	async fn should_roll_back_to_stored_state_on_failed_append() {
+		let csv_path = format!("{}.csv", temp_path("rollback"));
+		write_lines(&csv_path, 1..=3);
+		let db = FlakyDB::default();
+		// Stored before any line was read
+		db.put("", &task(&csv_path).get_state_dump()).unwrap();
+
+		// The records lost with the failed append are produced again
+		let mut service = TaskService::new(task(&csv_path), Box::new(db.clone()));
+		service.run().await;
+		let ids: Vec<usize> = db.records.lock().unwrap().iter().map(|x| x.id).collect();
+		assert_eq!(ids, vec![1, 2, 3]);
+		assert!(db.failed.load(Ordering::SeqCst));
 
-	// todo tmp shortcut for poc
-	pub async fn get_chunk(&mut self, offset: u64, limit: u64) -> Vec<TaskResponse> {
-		self.task.run(Some(offset), Some(limit)).await
+		fs::remove_file(csv_path).unwrap();
 	}
 }
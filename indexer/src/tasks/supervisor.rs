@@ -0,0 +1,148 @@
+use std::collections::HashSet;
+use std::error::Error;
+
+use tracing::{error, info};
+
+use crate::storage::types::BaseKVStorage;
+use crate::tasks::service::TaskService;
+use crate::tasks::types::BaseTask;
+
+/// Runs every task on its own tokio task, each persisting its state under its own id,
+/// and all of them appending to the shared event log.
+#[derive(Default)]
+pub struct TaskSupervisor {
+	services: Vec<TaskService>,
+	ids: HashSet<String>,
+}
+
+impl TaskSupervisor {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	// tasks sharing an id would overwrite each other's state
+	pub fn add(
+		&mut self, task: Box<dyn BaseTask>, db: Box<dyn BaseKVStorage>,
+	) -> Result<(), Box<dyn Error>> {
+		let task_id = task.get_id();
+		if !self.ids.insert(task_id.clone()) {
+			return Err(format!("Duplicate task id={}", task_id).into());
+		}
+		self.services.push(TaskService::new(task, db));
+		Ok(())
+	}
+
+	/// Run the tasks until all of them are finished.
+	pub async fn run(self) {
+		let handles: Vec<_> = self
+			.services
+			.into_iter()
+			.map(|mut service| {
+				let task_id = service.get_id();
+				(task_id, tokio::spawn(async move { service.run().await }))
+			})
+			.collect();
+		info!("Supervising {} tasks", handles.len());
+
+		for (task_id, handle) in handles {
+			if let Err(e) = handle.await {
+				error!("Job id={} stopped: {}", task_id, e);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+	use std::time::Duration;
+
+	use tokio::sync::Barrier;
+	use tokio::time::timeout;
+
+	use super::*;
+	use crate::storage::mock_db::MockDBClient;
+	use crate::tasks::types::{BaseTaskState, TaskResponse};
+
+	/// Task finishing after a single run, once every task sharing the barrier runs as well.
+	struct StubTask {
+		id: String,
+		barrier: Arc<Barrier>,
+		is_finished: bool,
+	}
+
+	impl StubTask {
+		fn new(id: &str, barrier: Arc<Barrier>) -> Box<Self> {
+			Box::new(Self { id: id.to_string(), barrier, is_finished: false })
+		}
+	}
+
+	#[tonic::async_trait]
+	impl BaseTask for StubTask {
+		async fn run(&mut self, _offset: Option<u64>, _limit: Option<u64>) -> Vec<TaskResponse> {
+			self.barrier.wait().await;
+			self.is_finished = true;
+			Vec::new()
+		}
+
+		fn get_sleep_interval(&self) -> Duration {
+			Duration::from_secs(0)
+		}
+
+		fn get_state(&self) -> BaseTaskState {
+			BaseTaskState { is_synced: true, is_finished: self.is_finished, records_total: 0 }
+		}
+
+		fn get_id(&self) -> String {
+			self.id.clone()
+		}
+
+		fn get_is_finished(&self) -> bool {
+			self.is_finished
+		}
+
+		fn get_state_dump(&self) -> String {
+			serde_json::to_string(&self.is_finished).unwrap()
+		}
+
+		fn restore_state(&mut self, dump: &str) -> serde_json::Result<()> {
+			self.is_finished = serde_json::from_str(dump)?;
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn should_reject_duplicate_task_ids() {
+		let barrier = Arc::new(Barrier::new(2));
+		let mut supervisor = TaskSupervisor::new();
+		supervisor
+			.add(
+				StubTask::new("a", barrier.clone()),
+				Box::new(MockDBClient::new()),
+			)
+			.unwrap();
+		let res = supervisor.add(
+			StubTask::new("a", barrier.clone()),
+			Box::new(MockDBClient::new()),
+		);
+		assert!(res.is_err());
+		supervisor.add(StubTask::new("b", barrier), Box::new(MockDBClient::new())).unwrap();
+		assert_eq!(supervisor.services.len(), 2);
+	}
+
+	#[tokio::test]
+	async fn should_run_tasks_concurrently() {
+		// Neither task finishes unless the other one runs at the same time
+		let barrier = Arc::new(Barrier::new(2));
+		let mut supervisor = TaskSupervisor::new();
+		supervisor
+			.add(
+				StubTask::new("a", barrier.clone()),
+				Box::new(MockDBClient::new()),
+			)
+			.unwrap();
+		supervisor.add(StubTask::new("b", barrier), Box::new(MockDBClient::new())).unwrap();
+
+		timeout(Duration::from_secs(5), supervisor.run()).await.expect("tasks ran one at a time");
+	}
+}
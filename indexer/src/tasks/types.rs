@@ -2,9 +2,11 @@ use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use proto_buf::indexer::EventKind as EventKindPb;
+
 // todo better layer separation
 #[tonic::async_trait]
-pub trait BaseTask {
+pub trait BaseTask: Send + Sync {
 	// todo offset and limit are tmp args for POC, remove after
 	async fn run(&mut self, offset: Option<u64>, limit: Option<u64>) -> Vec<TaskResponse>;
 
@@ -20,6 +22,9 @@ pub trait BaseTask {
 
 	// get serialized state to store to a db
 	fn get_state_dump(&self) -> String;
+
+	// restore the state from a `get_state_dump` output, to resume after a restart
+	fn restore_state(&mut self, dump: &str) -> serde_json::Result<()>;
 }
 
 // todo, dublicate for proto struct, remove once settled
@@ -31,6 +36,35 @@ pub struct TaskResponse {
 	pub job_id: String,
 	pub data: String,
 	pub schema_id: usize,
+	// address of the attestation source, the registry contract for on-chain events
+	#[serde(default)]
+	pub source_address: String,
+	#[serde(default)]
+	pub kind: EventKind,
+	// registry id, to match updates and revocations with the recorded attestation
+	#[serde(default)]
+	pub attestation_id: String,
+	// compensates an event of an orphaned block
+	#[serde(default)]
+	pub retraction: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventKind {
+	#[default]
+	Recorded,
+	Updated,
+	Revoked,
+}
+
+impl From<EventKind> for EventKindPb {
+	fn from(value: EventKind) -> Self {
+		match value {
+			EventKind::Recorded => Self::Recorded,
+			EventKind::Updated => Self::Updated,
+			EventKind::Revoked => Self::Revoked,
+		}
+	}
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -0,0 +1,56 @@
+use std::env;
+
+use crate::decay::DecayPolicy;
+use crate::error::LcError;
+
+/// How the attestations of an issuer about a subject combine, within a domain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Aggregation {
+	/// The latest attestation replaces the previous ones, in both forms,
+	/// so that a trust to distrust flip moves the weight over.
+	#[default]
+	Latest,
+	/// The weights of all the attestations add up.
+	/// Only a single timestamp is kept for the sum, so it can't be combined with a window.
+	Accumulate,
+}
+
+impl Aggregation {
+	/// `LINEAR_COMBINER_AGGREGATION`, either `latest` (the default) or `accumulate`.
+	/// Accumulated opinions can't be dropped out of a window, the combination is rejected.
+	pub fn from_env(decay: &DecayPolicy) -> Result<Self, LcError> {
+		let aggregation = match env::var("LINEAR_COMBINER_AGGREGATION").as_deref() {
+			Err(_) | Ok("latest") => Self::Latest,
+			Ok("accumulate") => Self::Accumulate,
+			Ok(_) => return Err(LcError::ParseError),
+		};
+		match (aggregation, decay) {
+			(Self::Accumulate, DecayPolicy::Window(_)) => Err(LcError::ParseError),
+			_ => Ok(aggregation),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_reject_accumulating_within_a_window() {
+		env::set_var("LINEAR_COMBINER_AGGREGATION", "accumulate");
+		let window = DecayPolicy::Window(100);
+		assert!(matches!(
+			Aggregation::from_env(&window),
+			Err(LcError::ParseError)
+		));
+		let half_life = DecayPolicy::HalfLife(100);
+		assert_eq!(
+			Aggregation::from_env(&half_life).unwrap(),
+			Aggregation::Accumulate
+		);
+
+		env::set_var("LINEAR_COMBINER_AGGREGATION", "latest");
+		assert_eq!(Aggregation::from_env(&window).unwrap(), Aggregation::Latest);
+		env::remove_var("LINEAR_COMBINER_AGGREGATION");
+	}
+}
@@ -0,0 +1,111 @@
+use std::env;
+
+use crate::error::LcError;
+use crate::item::LtItem;
+
+/// How the weight of an opinion decreases with its age.
+/// Ages are measured in the unit of the term timestamps,
+/// relative to the latest timestamp seen by the combiner.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DecayPolicy {
+	/// Opinions keep their weight forever.
+	#[default]
+	None,
+	/// Opinions lose half of their weight every given period.
+	HalfLife(u64),
+	/// Opinions last updated more than the given period ago are dropped.
+	/// Only with the latest attestation aggregation, see `Aggregation::from_env`.
+	Window(u64),
+}
+
+impl DecayPolicy {
+	/// Either `LINEAR_COMBINER_HALF_LIFE` or `LINEAR_COMBINER_WINDOW`, if set.
+	pub fn from_env() -> Result<Self, LcError> {
+		let period = |name: &str| {
+			env::var(name)
+				.ok()
+				.map(|x| x.parse::<u64>().map_err(|_| LcError::ParseError))
+				.transpose()
+		};
+		match (
+			period("LINEAR_COMBINER_HALF_LIFE")?,
+			period("LINEAR_COMBINER_WINDOW")?,
+		) {
+			(None, None) => Ok(Self::None),
+			(Some(half_life), None) if half_life > 0 => Ok(Self::HalfLife(half_life)),
+			(None, Some(window)) => Ok(Self::Window(window)),
+			_ => Err(LcError::ParseError),
+		}
+	}
+
+	pub fn is_none(&self) -> bool {
+		matches!(self, Self::None)
+	}
+
+	/// Weight left to an opinion of the given age.
+	pub fn factor(&self, age: u64) -> f32 {
+		match self {
+			Self::None => 1.,
+			Self::HalfLife(half_life) => 0.5f64.powf(age as f64 / *half_life as f64) as f32,
+			Self::Window(window) if age > *window => 0.,
+			Self::Window(_) => 1.,
+		}
+	}
+
+	/// Add a weight to an accumulated value, returning the new value and its timestamp.
+	/// With a half-life both are first brought to the later of the two timestamps,
+	/// so that every accumulated opinion decays from its own timestamp.
+	/// Otherwise the weights add up, under the latest timestamp,
+	/// which is why a window doesn't apply to accumulated opinions.
+	/// Negative weights retract earlier ones, down to zero.
+	pub fn accumulate(&self, value: f32, timestamp: u64, weight: f32, at: u64) -> (f32, u64) {
+		let latest = timestamp.max(at);
+		let value = match self {
+			Self::HalfLife(_) => {
+				value * self.factor(latest - timestamp) + weight * self.factor(latest - at)
+			},
+			_ => value + weight,
+		};
+		(value.max(0.), latest)
+	}
+
+	/// The item with its value decayed from its timestamp to `now`.
+	pub fn apply(&self, mut item: LtItem, now: u64) -> LtItem {
+		item.value *= self.factor(now.saturating_sub(item.timestamp));
+		item
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn should_halve_every_half_life() {
+		let decay = DecayPolicy::HalfLife(100);
+		assert_eq!(decay.factor(0), 1.);
+		assert_eq!(decay.factor(100), 0.5);
+		assert_eq!(decay.factor(200), 0.25);
+
+		// An opinion of 8 at 0, and one of 2 at 200
+		let (value, timestamp) = decay.accumulate(8., 0, 2., 200);
+		assert_eq!((value, timestamp), (4., 200));
+		// Older weights decay to the latest timestamp too
+		let (value, timestamp) = decay.accumulate(value, timestamp, 4., 100);
+		assert_eq!((value, timestamp), (6., 200));
+
+		let item = decay.apply(LtItem::new(0, 1, value, timestamp), 300);
+		assert_eq!(item, LtItem::new(0, 1, 3., 200));
+	}
+
+	#[test]
+	fn should_drop_opinions_out_of_window() {
+		let decay = DecayPolicy::Window(100);
+		assert_eq!(decay.accumulate(8., 0, 2., 50), (10., 50));
+
+		let item = LtItem::new(0, 1, 10., 50);
+		assert_eq!(decay.apply(item.clone(), 150).value, 10.);
+		assert_eq!(decay.apply(item.clone(), 151).value, 0.);
+		assert_eq!(DecayPolicy::None.apply(item.clone(), u64::MAX), item);
+	}
+}
@@ -5,7 +5,7 @@ pub struct LtItem {
 	x: u32,
 	y: u32,
 	pub(crate) value: f32,
-	timestamp: u64,
+	pub(crate) timestamp: u64,
 }
 
 impl Default for LtItem {
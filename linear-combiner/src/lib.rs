@@ -0,0 +1,5 @@
+pub mod aggregation;
+pub mod decay;
+pub mod error;
+pub mod item;
+pub mod managers;
@@ -10,24 +10,27 @@ use proto_buf::combiner::{LtBatch, LtHistoryBatch, LtObject, Mapping, MappingQue
 use proto_buf::common::Void;
 use proto_buf::transformer::TermObject;
 
-use crate::error::LcError;
-use crate::managers::checkpoint::CheckpointManager;
-use crate::managers::index::IndexManager;
-use crate::managers::item::ItemManager;
-use crate::managers::mapping::MappingManager;
-use crate::managers::update::UpdateManager;
-
-pub mod error;
-pub mod item;
-pub mod managers;
+use linear_combiner::aggregation::Aggregation;
+use linear_combiner::decay::DecayPolicy;
+use linear_combiner::error::LcError;
+use linear_combiner::item::LtItem;
+use linear_combiner::managers::checkpoint::CheckpointManager;
+use linear_combiner::managers::item::ItemManager;
+use linear_combiner::managers::mapping::MappingManager;
+use linear_combiner::managers::term::TermManager;
+use linear_combiner::managers::update::UpdateManager;
 
 #[derive(Clone)]
 struct LinearCombinerService {
 	db_url: String,
+	aggregation: Aggregation,
+	decay: DecayPolicy,
 }
 
 impl LinearCombinerService {
-	pub fn new(db_url: &str) -> Result<Self, LcError> {
+	pub fn new(
+		db_url: &str, aggregation: Aggregation, decay: DecayPolicy,
+	) -> Result<Self, LcError> {
 		let mut opts = Options::default();
 		opts.create_missing_column_families(true);
 		opts.create_if_missing(true);
@@ -39,7 +42,7 @@ impl LinearCombinerService {
 		.map_err(LcError::DbError)?;
 		CheckpointManager::init(&db)?;
 
-		Ok(Self { db_url: db_url.to_string() })
+		Ok(Self { db_url: db_url.to_string(), aggregation, decay })
 	}
 }
 
@@ -59,51 +62,13 @@ impl LinearCombiner for LinearCombinerService {
 		)
 		.map_err(|e| Status::internal(format!("Internal error: {}", e)))?;
 
-		let mut offset = CheckpointManager::read_checkpoint(&db)?;
-
 		let mut terms = Vec::new();
 		let mut stream = request.into_inner();
 		while let Some(term) = stream.message().await? {
 			terms.push(term);
 		}
 
-		for term in terms {
-			let domain = term.domain.to_be_bytes();
-			let form = term.form.to_be_bytes();
-
-			let (x, is_x_new) = IndexManager::get_index(&db, term.from.clone(), offset)?;
-
-			// If x is new, write new mapping and increment the offset
-			if is_x_new {
-				MappingManager::write_mapping(&db, x.to_vec(), term.from.clone())?;
-				offset += 1;
-			}
-			let (y, is_y_new) = IndexManager::get_index(&db, term.to.clone(), offset)?;
-
-			// If y is new, write new mapping and increment the offset
-			if is_y_new {
-				MappingManager::write_mapping(&db, y.to_vec(), term.to.clone())?;
-				offset += 1;
-			}
-
-			let mut key = Vec::new();
-			key.extend_from_slice(&domain);
-			key.extend_from_slice(&form);
-			key.extend_from_slice(&x);
-			key.extend_from_slice(&y);
-
-			println!(
-				"Received Item({}, {}, {})",
-				u32::from_be_bytes(x),
-				u32::from_be_bytes(y),
-				term.weight
-			);
-
-			let value = ItemManager::update_value(&db, key.clone(), term.weight, term.timestamp)?;
-			UpdateManager::set_value(&db, key.clone(), value, term.timestamp)?;
-		}
-
-		CheckpointManager::write_checkpoint(&db, offset)?;
+		TermManager::write_terms(&db, terms, &self.aggregation, &self.decay)?;
 
 		Ok(Response::new(Void {}))
 	}
@@ -117,13 +82,16 @@ impl LinearCombiner for LinearCombinerService {
 
 		let mappings = MappingManager::read_mappings(&db, mapping_query.start, mapping_query.size)?;
 
-		let (tx, rx) = channel(1);
-		for x in mappings.clone() {
-			let x_obj: Mapping = x.into();
-			if let Err(e) = tx.send(Ok(x_obj)).await {
-				e.0?;
+		let (tx, rx) = channel(4);
+		tokio::spawn(async move {
+			for x in mappings {
+				let x_obj: Mapping = x.into();
+				if tx.send(Ok(x_obj)).await.is_err() {
+					break;
+				}
 			}
-		}
+		});
+
 		Ok(Response::new(ReceiverStream::new(rx)))
 	}
 
@@ -131,23 +99,41 @@ impl LinearCombiner for LinearCombinerService {
 		&self, request: Request<LtBatch>,
 	) -> Result<Response<Self::GetNewDataStream>, Status> {
 		let batch = request.into_inner();
-		let db = DB::open_cf(&Options::default(), &self.db_url, vec!["update"])
-			.map_err(|e| Status::internal(format!("Internal error: {}", e)))?;
+		let db = DB::open_cf(
+			&Options::default(),
+			&self.db_url,
+			vec!["checkpoint", "update"],
+		)
+		.map_err(|e| Status::internal(format!("Internal error: {}", e)))?;
+		let now = CheckpointManager::read_timestamp(&db)?;
 
 		let mut prefix = Vec::new();
 		prefix.extend_from_slice(&batch.domain.to_be_bytes());
 		prefix.extend_from_slice(&batch.form.to_be_bytes());
 		let items = UpdateManager::read_batch(&db, prefix.clone(), batch.size)?;
+		drop(db);
 
-		let (tx, rx) = channel(1);
-		for x in items.clone() {
-			let x_obj: LtObject = x.into();
-			if let Err(e) = tx.send(Ok(x_obj)).await {
-				e.0?;
+		let db_url = self.db_url.clone();
+		let decay = self.decay;
+		let (tx, rx) = channel(4);
+		tokio::spawn(async move {
+			for x in items.clone() {
+				let x_obj: LtObject = decay.apply(x, now).into();
+				if tx.send(Ok(x_obj)).await.is_err() {
+					return;
+				}
 			}
-		}
 
-		UpdateManager::delete_batch(&db, prefix, items)?;
+			// The updates are only consumed once streamed, before the stream ends.
+			// Otherwise they are served again
+			let res = DB::open_cf(&Options::default(), &db_url, vec!["update"])
+				.map_err(LcError::DbError)
+				.and_then(|db| UpdateManager::delete_batch(&db, prefix, items));
+			if let Err(e) = res {
+				println!("Failed to consume the streamed updates: {}", e);
+			}
+			drop(tx);
+		});
 
 		Ok(Response::new(ReceiverStream::new(rx)))
 	}
@@ -156,8 +142,14 @@ impl LinearCombiner for LinearCombinerService {
 		&self, request: Request<LtHistoryBatch>,
 	) -> Result<Response<Self::GetHistoricDataStream>, Status> {
 		let batch = request.into_inner();
-		let db = DB::open_cf_for_read_only(&Options::default(), &self.db_url, vec!["item"], false)
-			.map_err(|e| Status::internal(format!("Internal error: {}", e)))?;
+		let db = DB::open_cf_for_read_only(
+			&Options::default(),
+			&self.db_url,
+			vec!["checkpoint", "item"],
+			false,
+		)
+		.map_err(|e| Status::internal(format!("Internal error: {}", e)))?;
+		let now = CheckpointManager::read_timestamp(&db)?;
 
 		let is_x_bigger = batch.x0 <= batch.x1;
 		let is_y_bigger = batch.y0 <= batch.y1;
@@ -178,7 +170,11 @@ impl LinearCombiner for LinearCombinerService {
 		prefix.extend_from_slice(&domain_bytes);
 		prefix.extend_from_slice(&form_bytes);
 
-		let items = ItemManager::read_window(&db, prefix, (x_start, y_start), (x_end, y_end))?;
+		let items: Vec<LtItem> =
+			ItemManager::read_window(&db, prefix, (x_start, y_start), (x_end, y_end))?
+				.into_iter()
+				.map(|x| self.decay.apply(x, now))
+				.collect();
 
 		println!("Read items: {:?}", items);
 
@@ -197,7 +193,11 @@ impl LinearCombiner for LinearCombinerService {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
 	let addr = "[::1]:50052".parse()?;
-	let service = LinearCombinerService::new("lc-storage")?;
+	// The latest attestation counts, unless `LINEAR_COMBINER_AGGREGATION` is `accumulate`.
+	// Opinions decay as per `LINEAR_COMBINER_HALF_LIFE` or `LINEAR_COMBINER_WINDOW`, if set
+	let decay = DecayPolicy::from_env()?;
+	let aggregation = Aggregation::from_env(&decay)?;
+	let service = LinearCombinerService::new("lc-storage", aggregation, decay)?;
 	Server::builder().add_service(LinearCombinerServer::new(service)).serve(addr).await?;
 	Ok(())
 }
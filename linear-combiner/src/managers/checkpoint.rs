@@ -34,6 +34,24 @@ impl CheckpointManager {
 		db.put_cf(&cf, b"participant_count", count.to_be_bytes()).map_err(LcError::DbError)?;
 		Ok(())
 	}
+
+	/// Latest term timestamp seen, which opinions are decayed relative to.
+	pub fn read_timestamp(db: &DB) -> Result<u64, LcError> {
+		let cf = db.cf_handle("checkpoint").ok_or(LcError::NotFoundError)?;
+		let timestamp_bytes_opt = db.get_cf(&cf, b"latest_timestamp").map_err(LcError::DbError)?;
+		let timestamp_bytes = timestamp_bytes_opt.map_or([0; 8], |x| {
+			let mut bytes: [u8; 8] = [0; 8];
+			bytes.copy_from_slice(&x);
+			bytes
+		});
+		Ok(u64::from_be_bytes(timestamp_bytes))
+	}
+
+	pub fn write_timestamp(db: &DB, timestamp: u64) -> Result<(), LcError> {
+		let cf = db.cf_handle("checkpoint").ok_or(LcError::NotFoundError)?;
+		db.put_cf(&cf, b"latest_timestamp", timestamp.to_be_bytes()).map_err(LcError::DbError)?;
+		Ok(())
+	}
 }
 
 #[cfg(test)]
@@ -52,5 +70,8 @@ mod test {
 		CheckpointManager::write_checkpoint(&db, 15).unwrap();
 		let checkpoint = CheckpointManager::read_checkpoint(&db).unwrap();
 		assert_eq!(checkpoint, 15);
+
+		CheckpointManager::write_timestamp(&db, 2397848).unwrap();
+		assert_eq!(CheckpointManager::read_timestamp(&db).unwrap(), 2397848);
 	}
 }
@@ -1,5 +1,6 @@
-use rocksdb::DB;
+use rocksdb::{IteratorMode, DB};
 
+use crate::decay::DecayPolicy;
 use crate::error::LcError;
 use crate::item::LtItem;
 
@@ -14,20 +15,44 @@ impl ItemManager {
 		Ok(item)
 	}
 
+	pub fn set_value(db: &DB, key: Vec<u8>, value: f32, timestamp: u64) -> Result<(), LcError> {
+		let cf = db.cf_handle("item").ok_or(LcError::NotFoundError)?;
+		let mut bytes = Vec::new();
+		bytes.extend_from_slice(&value.to_be_bytes());
+		bytes.extend_from_slice(&timestamp.to_be_bytes());
+		db.put_cf(&cf, key, bytes).map_err(LcError::DbError)?;
+		Ok(())
+	}
+
+	/// Add the weight to the item, as per the decay policy.
+	/// Returns the new value, along with its timestamp.
 	pub fn update_value(
-		db: &DB, key: Vec<u8>, weight: f32, timestamp: u64,
-	) -> Result<f32, LcError> {
+		db: &DB, key: Vec<u8>, weight: f32, timestamp: u64, decay: &DecayPolicy,
+	) -> Result<(f32, u64), LcError> {
 		let cf = db.cf_handle("item").ok_or(LcError::NotFoundError)?;
 		let item = Self::get_value(db, &key)?;
 
-		let new_value = item.value + weight;
+		let (new_value, new_timestamp) =
+			decay.accumulate(item.value, item.timestamp, weight, timestamp);
 
 		let mut bytes = Vec::new();
 		bytes.extend_from_slice(&new_value.to_be_bytes());
-		bytes.extend_from_slice(&timestamp.to_be_bytes());
+		bytes.extend_from_slice(&new_timestamp.to_be_bytes());
 
 		db.put_cf(&cf, key.clone(), bytes).map_err(LcError::DbError)?;
-		Ok(new_value)
+		Ok((new_value, new_timestamp))
+	}
+
+	/// All the items, along with their key.
+	pub fn read_all(db: &DB) -> Result<Vec<(Vec<u8>, LtItem)>, LcError> {
+		let cf = db.cf_handle("item").ok_or(LcError::NotFoundError)?;
+		db.iterator_cf(&cf, IteratorMode::Start)
+			.map(|item| {
+				let (key, value) = item.map_err(LcError::DbError)?;
+				let lt_item = LtItem::from_raw(&key, &value);
+				Ok((key.to_vec(), lt_item))
+			})
+			.collect()
 	}
 
 	pub fn read_window(
@@ -74,7 +99,9 @@ mod test {
 		let weight = 50.;
 		let timestamp = 0;
 
-		let new_value = ItemManager::update_value(&db, key.clone(), weight, timestamp).unwrap();
+		let (new_value, _) =
+			ItemManager::update_value(&db, key.clone(), weight, timestamp, &DecayPolicy::None)
+				.unwrap();
 		let item = ItemManager::get_value(&db, &key).unwrap();
 
 		assert_eq!(item.value, new_value);
@@ -111,8 +138,10 @@ mod test {
 
 		let prev_item1 = ItemManager::get_value(&db, &key1).unwrap();
 		let prev_item2 = ItemManager::get_value(&db, &key2).unwrap();
-		ItemManager::update_value(&db, key1.clone(), weight, timestamp).unwrap();
-		ItemManager::update_value(&db, key2.clone(), weight, timestamp).unwrap();
+		ItemManager::update_value(&db, key1.clone(), weight, timestamp, &DecayPolicy::None)
+			.unwrap();
+		ItemManager::update_value(&db, key2.clone(), weight, timestamp, &DecayPolicy::None)
+			.unwrap();
 		let new_item1 = LtItem::new(x1, y1, prev_item1.value + weight, timestamp);
 		let new_item2 = LtItem::new(x2, y2, prev_item2.value + weight, timestamp);
 		let new_items = vec![new_item1, new_item2];
@@ -2,4 +2,5 @@ pub mod checkpoint;
 pub mod index;
 pub mod item;
 pub mod mapping;
+pub mod term;
 pub mod update;
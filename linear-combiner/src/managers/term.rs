@@ -0,0 +1,367 @@
+use std::collections::HashSet;
+
+use rocksdb::DB;
+
+use proto_buf::transformer::{Form, TermKind, TermObject};
+
+use crate::aggregation::Aggregation;
+use crate::decay::DecayPolicy;
+use crate::error::LcError;
+use crate::item::LtItem;
+use crate::managers::checkpoint::CheckpointManager;
+use crate::managers::index::IndexManager;
+use crate::managers::item::ItemManager;
+use crate::managers::mapping::MappingManager;
+use crate::managers::update::UpdateManager;
+
+#[derive(Debug)]
+pub struct TermManager;
+
+impl TermManager {
+	/// Combine the terms into the local trust, indexing the DIDs seen for the first time.
+	/// Returns the updated items with their new values, decayed to the latest timestamp,
+	/// along with their domain and form.
+	/// As the latest timestamp advances, the items whose decayed value changed are
+	/// queued again as well, so that the readers of the updates see every opinion age.
+	pub fn write_terms(
+		db: &DB, terms: Vec<TermObject>, aggregation: &Aggregation, decay: &DecayPolicy,
+	) -> Result<Vec<(u32, i32, LtItem)>, LcError> {
+		let mut offset = CheckpointManager::read_checkpoint(db)?;
+		let prev_timestamp = CheckpointManager::read_timestamp(db)?;
+		let now = terms.iter().map(|x| x.timestamp).fold(prev_timestamp, u64::max);
+
+		let mut items = Vec::new();
+		for term in terms {
+			let domain = term.domain.to_be_bytes();
+			let form = term.form.to_be_bytes();
+
+			let (x, is_x_new) = IndexManager::get_index(db, term.from.clone(), offset)?;
+
+			// If x is new, write new mapping and increment the offset
+			if is_x_new {
+				MappingManager::write_mapping(db, x.to_vec(), term.from.clone())?;
+				offset += 1;
+			}
+			let (y, is_y_new) = IndexManager::get_index(db, term.to.clone(), offset)?;
+
+			// If y is new, write new mapping and increment the offset
+			if is_y_new {
+				MappingManager::write_mapping(db, y.to_vec(), term.to.clone())?;
+				offset += 1;
+			}
+
+			let mut key = Vec::new();
+			key.extend_from_slice(&domain);
+			key.extend_from_slice(&form);
+			key.extend_from_slice(&x);
+			key.extend_from_slice(&y);
+
+			let x = u32::from_be_bytes(x);
+			let y = u32::from_be_bytes(y);
+			println!("Received Item({}, {}, {})", x, y, term.weight);
+
+			let is_revoked = term.kind() == TermKind::Revoked;
+			match aggregation {
+				Aggregation::Latest if is_revoked => {
+					items.extend(Self::retract(db, &term, key, x, y)?)
+				},
+				Aggregation::Latest => items.extend(Self::replace(db, &term, key, x, y)?),
+				Aggregation::Accumulate => {
+					let weight = if is_revoked { -term.weight } else { term.weight };
+					let (value, timestamp) =
+						ItemManager::update_value(db, key.clone(), weight, term.timestamp, decay)?;
+					UpdateManager::set_value(db, key, value, timestamp)?;
+					items.push((term.domain, term.form, LtItem::new(x, y, value, timestamp)));
+				},
+			}
+		}
+
+		CheckpointManager::write_checkpoint(db, offset)?;
+		CheckpointManager::write_timestamp(db, now)?;
+
+		if !decay.is_none() && now > prev_timestamp {
+			let updated: HashSet<(u32, i32, Vec<u8>)> = items
+				.iter()
+				.map(|(domain, form, item)| (*domain, *form, item.key_bytes()))
+				.collect();
+			for (key, item) in ItemManager::read_all(db)? {
+				let (domain, form) = split_key(&key)?;
+				let is_aged = decay.apply(item.clone(), prev_timestamp).value
+					!= decay.apply(item.clone(), now).value;
+				if !is_aged || updated.contains(&(domain, form, item.key_bytes())) {
+					continue;
+				}
+				UpdateManager::set_value(db, key, item.value, item.timestamp)?;
+				items.push((domain, form, item));
+			}
+		}
+
+		Ok(items
+			.into_iter()
+			.map(|(domain, form, item)| (domain, form, decay.apply(item, now)))
+			.collect())
+	}
+
+	/// Make the term the contribution of its truster to the trustee in the domain,
+	/// clearing the opposite form if an earlier attestation set it.
+	/// Terms older than the latest contribution, in either form, are stale and skipped.
+	fn replace(
+		db: &DB, term: &TermObject, key: Vec<u8>, x: u32, y: u32,
+	) -> Result<Vec<(u32, i32, LtItem)>, LcError> {
+		let other_form = if term.form == i32::from(Form::Trust) {
+			i32::from(Form::Distrust)
+		} else {
+			i32::from(Form::Trust)
+		};
+		let mut other_key = key.clone();
+		other_key[4..8].copy_from_slice(&other_form.to_be_bytes());
+
+		let item = ItemManager::get_value(db, &key)?;
+		let other = ItemManager::get_value(db, &other_key)?;
+		if term.timestamp < item.timestamp || term.timestamp < other.timestamp {
+			return Ok(Vec::new());
+		}
+
+		ItemManager::set_value(db, key.clone(), term.weight, term.timestamp)?;
+		UpdateManager::set_value(db, key, term.weight, term.timestamp)?;
+		let mut items = vec![(
+			term.domain,
+			term.form,
+			LtItem::new(x, y, term.weight, term.timestamp),
+		)];
+
+		// The weight moves over to the form of the latest attestation
+		if other.timestamp <= term.timestamp && other.value != 0. {
+			ItemManager::set_value(db, other_key.clone(), 0., term.timestamp)?;
+			UpdateManager::set_value(db, other_key, 0., term.timestamp)?;
+			items.push((
+				term.domain,
+				other_form,
+				LtItem::new(x, y, 0., term.timestamp),
+			));
+		}
+		Ok(items)
+	}
+
+	/// Remove the contribution of a revoked term, unless a later attestation replaced it.
+	fn retract(
+		db: &DB, term: &TermObject, key: Vec<u8>, x: u32, y: u32,
+	) -> Result<Vec<(u32, i32, LtItem)>, LcError> {
+		let item = ItemManager::get_value(db, &key)?;
+		if item.timestamp != term.timestamp || item.value == 0. {
+			return Ok(Vec::new());
+		}
+
+		// The timestamp is kept, so that older attestations remain stale
+		ItemManager::set_value(db, key.clone(), 0., term.timestamp)?;
+		UpdateManager::set_value(db, key, 0., term.timestamp)?;
+		Ok(vec![(
+			term.domain,
+			term.form,
+			LtItem::new(x, y, 0., term.timestamp),
+		)])
+	}
+}
+
+/// Domain and form of an item key.
+fn split_key(key: &[u8]) -> Result<(u32, i32), LcError> {
+	let domain = key.get(..4).and_then(|x| x.try_into().ok()).ok_or(LcError::ParseError)?;
+	let form = key.get(4..8).and_then(|x| x.try_into().ok()).ok_or(LcError::ParseError)?;
+	Ok((u32::from_be_bytes(domain), i32::from_be_bytes(form)))
+}
+
+#[cfg(test)]
+mod test {
+	use rocksdb::{Options, DB};
+
+	use super::*;
+
+	#[test]
+	fn should_index_and_combine_terms() {
+		let mut opts = Options::default();
+		opts.create_missing_column_families(true);
+		opts.create_if_missing(true);
+		let db = DB::open_cf(
+			&opts,
+			"lc-wt-test-storage",
+			vec!["checkpoint", "index", "item", "mapping", "update"],
+		)
+		.unwrap();
+		CheckpointManager::init(&db).unwrap();
+
+		let term = |from: &str, to: &str| TermObject {
+			from: from.to_string(),
+			to: to.to_string(),
+			weight: 50.,
+			domain: 2,
+			form: 0,
+			timestamp: 0,
+
+			..TermObject::default()
+		};
+		let offset = CheckpointManager::read_checkpoint(&db).unwrap();
+		let items = TermManager::write_terms(
+			&db,
+			vec![term("wt-alice", "wt-bob"), term("wt-alice", "wt-bob")],
+			&Aggregation::Accumulate,
+			&DecayPolicy::None,
+		)
+		.unwrap();
+
+		assert_eq!(CheckpointManager::read_checkpoint(&db).unwrap(), offset + 2);
+		assert_eq!(items.len(), 2);
+		let (domain, form, item) = &items[1];
+		assert_eq!((*domain, *form), (2, 0));
+		assert_eq!(
+			item,
+			&LtItem::new(offset, offset + 1, items[0].2.value + 50., 0)
+		);
+	}
+
+	#[test]
+	fn should_queue_aged_items_as_time_passes() {
+		let mut opts = Options::default();
+		opts.create_missing_column_families(true);
+		opts.create_if_missing(true);
+		let db = DB::open_cf(
+			&opts,
+			"lc-wtd-test-storage",
+			vec!["checkpoint", "index", "item", "mapping", "update"],
+		)
+		.unwrap();
+		CheckpointManager::init(&db).unwrap();
+
+		let decay = DecayPolicy::HalfLife(100);
+		let term = |from: &str, to: &str, timestamp: u64| TermObject {
+			from: from.to_string(),
+			to: to.to_string(),
+			weight: 50.,
+			domain: 2,
+			form: 0,
+			timestamp,
+
+			..TermObject::default()
+		};
+		let write = |terms| TermManager::write_terms(&db, terms, &Aggregation::Latest, &decay);
+		// Reads (and consumes) the queued updates, as the core computer syncs
+		let sync = || {
+			let prefix = vec![0, 0, 0, 2, 0, 0, 0, 0];
+			let items = UpdateManager::read_batch(&db, prefix.clone(), 100).unwrap();
+			UpdateManager::delete_batch(&db, prefix, items.clone()).unwrap();
+			let now = CheckpointManager::read_timestamp(&db).unwrap();
+			let mut values: Vec<f32> =
+				items.into_iter().map(|x| decay.apply(x, now).value).collect();
+			values.sort_by(f32::total_cmp);
+			values
+		};
+
+		write(vec![term("wtd-alice", "wtd-bob", 0)]).unwrap();
+		assert_eq!(sync(), vec![50.]);
+
+		// The untouched opinion of bob ages along with the new ones
+		let items = write(vec![
+			term("wtd-alice", "wtd-carol", 100),
+			term("wtd-alice", "wtd-dave", 0),
+		])
+		.unwrap();
+		assert_eq!(CheckpointManager::read_timestamp(&db).unwrap(), 100);
+		let values: Vec<f32> = items.iter().map(|(_, _, x)| x.value).collect();
+		assert_eq!(values, vec![50., 25., 25.]);
+		assert_eq!(sync(), vec![25., 25., 50.]);
+
+		// Nothing ages if time doesn't pass
+		write(vec![term("wtd-alice", "wtd-carol", 100)]).unwrap();
+		assert_eq!(sync(), vec![50.]);
+	}
+
+	#[test]
+	fn should_replace_with_latest_attestation() {
+		let mut opts = Options::default();
+		opts.create_missing_column_families(true);
+		opts.create_if_missing(true);
+		let db = DB::open_cf(
+			&opts,
+			"lc-wtl-test-storage",
+			vec!["checkpoint", "index", "item", "mapping", "update"],
+		)
+		.unwrap();
+		CheckpointManager::init(&db).unwrap();
+
+		let term = |form: Form, weight: f32, timestamp: u64| TermObject {
+			from: "wtl-alice".to_string(),
+			to: "wtl-bob".to_string(),
+			weight,
+			domain: 2,
+			form: form.into(),
+			timestamp,
+
+			..TermObject::default()
+		};
+		let write = |terms| {
+			TermManager::write_terms(&db, terms, &Aggregation::Latest, &DecayPolicy::None).unwrap()
+		};
+
+		// Re-signing the same attestation doesn't add up
+		let items = write(vec![term(Form::Trust, 50., 1), term(Form::Trust, 50., 2)]);
+		assert_eq!(items[1].2.value, 50.);
+
+		// A stale attestation is skipped
+		assert!(write(vec![term(Form::Trust, 10., 1)]).is_empty());
+
+		// A flip moves the weight over to distrust
+		let items = write(vec![term(Form::Distrust, 30., 3)]);
+		assert_eq!(items.len(), 2);
+		assert_eq!((items[0].1, items[0].2.value), (Form::Distrust.into(), 30.));
+		assert_eq!((items[1].1, items[1].2.value), (Form::Trust.into(), 0.));
+
+		// Older trust doesn't override the newer distrust
+		assert!(write(vec![term(Form::Trust, 50., 2)]).is_empty());
+
+		// A flip signed at the same time clears the distrust too
+		let items = write(vec![term(Form::Trust, 20., 3)]);
+		assert_eq!(items.len(), 2);
+		assert_eq!((items[0].1, items[0].2.value), (Form::Trust.into(), 20.));
+		assert_eq!((items[1].1, items[1].2.value), (Form::Distrust.into(), 0.));
+	}
+
+	#[test]
+	fn should_retract_revoked_terms() {
+		let mut opts = Options::default();
+		opts.create_missing_column_families(true);
+		opts.create_if_missing(true);
+		let db = DB::open_cf(
+			&opts,
+			"lc-wtr-test-storage",
+			vec!["checkpoint", "index", "item", "mapping", "update"],
+		)
+		.unwrap();
+		CheckpointManager::init(&db).unwrap();
+
+		let term = |kind: TermKind, timestamp: u64| TermObject {
+			from: "wtr-alice".to_string(),
+			to: "wtr-bob".to_string(),
+			weight: 50.,
+			domain: 2,
+			form: Form::Trust.into(),
+			timestamp,
+			kind: kind.into(),
+		};
+		let write = |terms, aggregation| {
+			TermManager::write_terms(&db, terms, &aggregation, &DecayPolicy::None).unwrap()
+		};
+
+		write(vec![term(TermKind::Recorded, 1)], Aggregation::Latest);
+		let items = write(vec![term(TermKind::Revoked, 1)], Aggregation::Latest);
+		assert_eq!(items.len(), 1);
+		assert_eq!(items[0].2.value, 0.);
+
+		// The tombstone of a replaced attestation is a no-op
+		write(vec![term(TermKind::Recorded, 2)], Aggregation::Latest);
+		write(vec![term(TermKind::Updated, 3)], Aggregation::Latest);
+		assert!(write(vec![term(TermKind::Revoked, 2)], Aggregation::Latest).is_empty());
+
+		// Accumulated weights are subtracted
+		let items = write(vec![term(TermKind::Revoked, 3)], Aggregation::Accumulate);
+		assert_eq!(items[0].2.value, 0.);
+	}
+}
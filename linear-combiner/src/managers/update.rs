@@ -33,15 +33,19 @@ impl UpdateManager {
 		})
 	}
 
+	/// Delete the read items, except for the ones updated again since.
 	pub fn delete_batch(db: &DB, prefix: Vec<u8>, items: Vec<LtItem>) -> Result<(), LcError> {
 		let cf = db.cf_handle("update").ok_or(LcError::NotFoundError)?;
 		let mut batch = WriteBatch::default();
-		items.iter().for_each(|x| {
+		for x in items {
 			let mut key = Vec::new();
 			key.extend_from_slice(&prefix);
 			key.extend_from_slice(&x.key_bytes());
-			batch.delete_cf(&cf, key);
-		});
+			let value = db.get_cf(&cf, &key).map_err(LcError::DbError)?;
+			if value.map_or(false, |value| LtItem::from_raw(&key, &value) == x) {
+				batch.delete_cf(&cf, key);
+			}
+		}
 		db.write(batch).map_err(LcError::DbError)?;
 		Ok(())
 	}
@@ -78,7 +82,15 @@ mod test {
 		assert_eq!(items, org_items);
 
 		UpdateManager::delete_batch(&db, prefix.clone(), items).unwrap();
-		let items = UpdateManager::read_batch(&db, prefix, 1).unwrap();
+		let items = UpdateManager::read_batch(&db, prefix.clone(), 1).unwrap();
 		assert_eq!(items, Vec::new());
+
+		// An update made after the read is kept
+		UpdateManager::set_value(&db, key.clone(), weight, timestamp).unwrap();
+		let items = UpdateManager::read_batch(&db, prefix.clone(), 1).unwrap();
+		UpdateManager::set_value(&db, key.clone(), 20., 1).unwrap();
+		UpdateManager::delete_batch(&db, prefix.clone(), items).unwrap();
+		let items = UpdateManager::read_batch(&db, prefix, 1).unwrap();
+		assert_eq!(items, vec![LtItem::new(0, 0, 20., 1)]);
 	}
 }
@@ -0,0 +1,22 @@
+use hex::FromHexError;
+use secp256k1::Error as SecpError;
+use serde_json::Error as SerdeError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum VcError {
+	#[error("SerdeError: {0}")]
+	SerdeError(SerdeError),
+
+	#[error("HexError: {0}")]
+	HexError(FromHexError),
+
+	#[error("SigVerificationError: {0}")]
+	SigVerificationError(SecpError),
+
+	#[error("VerificationError")]
+	VerificationError,
+
+	#[error("ParseError")]
+	ParseError,
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+pub mod error;
+pub mod proof;
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct VerifiableCredential {
@@ -44,7 +47,7 @@ pub struct StatusCredentialProof {
 	// pub signature: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TrustScoreCredential {
 	#[serde(rename = "@context")]
@@ -58,7 +61,7 @@ pub struct TrustScoreCredential {
 	pub proof: TrustScoreCredentialProof,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TrustScoreCredentialSubject {
 	pub id: String,
@@ -66,7 +69,7 @@ pub struct TrustScoreCredentialSubject {
 	pub trust_score: TrustScore,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TrustScore {
 	pub value: f64,
@@ -83,11 +86,13 @@ pub struct TrustScore {
 	pub scope: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct TrustScoreCredentialProof {}
+pub struct TrustScoreCredentialProof {
+	pub signature: String,
+}
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Manifest {
 	pub issuer: String,
@@ -100,11 +105,13 @@ pub struct Manifest {
 	pub proof: ManifestProof,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
-pub struct ManifestProof {}
+pub struct ManifestProof {
+	pub signature: String,
+}
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(untagged)]
 pub enum OneOrMore<T> {
 	One(T),
@@ -0,0 +1,179 @@
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use serde::Serialize;
+use serde_json::Value;
+use sha3::{Digest, Keccak256};
+
+use crate::error::VcError;
+use crate::{Manifest, TrustScoreCredential};
+
+/// Ethereum address of the public key, i.e. the last 20 bytes of its keccak hash.
+pub fn address_from_ecdsa_key(pub_key: &PublicKey) -> Vec<u8> {
+	let raw_pub_key = pub_key.serialize_uncompressed();
+	let pub_key_hash = Keccak256::digest(&raw_pub_key[1..]);
+	pub_key_hash[12..].to_vec()
+}
+
+/// `did:pkh:eth` DID of the public key.
+pub fn did_from_ecdsa_key(pub_key: &PublicKey) -> String {
+	format!(
+		"did:pkh:eth:0x{}",
+		hex::encode(address_from_ecdsa_key(pub_key))
+	)
+}
+
+/// Documents signed by their issuer.
+/// The signature covers the keccak hash of the JCS-canonicalized document, without its proof,
+/// and is encoded the same way as the attestations' ones: 0x-prefixed `r || s || v`.
+pub trait Signable: Serialize {
+	fn issuer(&self) -> &str;
+
+	fn signature(&self) -> &str;
+
+	fn set_signature(&mut self, signature: String);
+
+	fn get_message(&self) -> Result<Message, VcError> {
+		let mut value = serde_json::to_value(self).map_err(VcError::SerdeError)?;
+		if let Value::Object(fields) = &mut value {
+			fields.remove("proof");
+		}
+		let bytes = serde_jcs::to_vec(&value).map_err(VcError::SerdeError)?;
+		let digest = Keccak256::digest(bytes);
+		Message::from_digest_slice(digest.as_ref()).map_err(VcError::SigVerificationError)
+	}
+
+	/// Sign the document, which must be issued by the key's DID.
+	fn sign(&mut self, secret_key: &SecretKey) -> Result<(), VcError> {
+		let secp = Secp256k1::signing_only();
+		if did_from_ecdsa_key(&secret_key.public_key(&secp)) != self.issuer() {
+			return Err(VcError::VerificationError);
+		}
+
+		let signature = secp.sign_ecdsa_recoverable(&self.get_message()?, secret_key);
+		let (rec_id, rs_bytes) = signature.serialize_compact();
+		let mut sig_bytes = rs_bytes.to_vec();
+		sig_bytes.push(rec_id.to_i32() as u8 + 27);
+
+		self.set_signature(format!("0x{}", hex::encode(sig_bytes)));
+		Ok(())
+	}
+
+	/// Verify that the document was signed by its issuer.
+	fn verify(&self) -> Result<(), VcError> {
+		let sig_bytes =
+			hex::decode(self.signature().trim_start_matches("0x")).map_err(VcError::HexError)?;
+		if sig_bytes.len() != 65 {
+			return Err(VcError::ParseError);
+		}
+		let rec_id = match sig_bytes[64] {
+			0 | 27 => 0,
+			1 | 28 => 1,
+			_ => return Err(VcError::ParseError),
+		};
+		let rec_id = RecoveryId::from_i32(rec_id).map_err(VcError::SigVerificationError)?;
+		let signature = RecoverableSignature::from_compact(&sig_bytes[..64], rec_id)
+			.map_err(VcError::SigVerificationError)?;
+
+		let pk = signature.recover(&self.get_message()?).map_err(VcError::SigVerificationError)?;
+		if did_from_ecdsa_key(&pk) != self.issuer() {
+			return Err(VcError::VerificationError);
+		}
+		Ok(())
+	}
+}
+
+impl Signable for TrustScoreCredential {
+	fn issuer(&self) -> &str {
+		&self.issuer
+	}
+
+	fn signature(&self) -> &str {
+		&self.proof.signature
+	}
+
+	fn set_signature(&mut self, signature: String) {
+		self.proof.signature = signature;
+	}
+}
+
+impl Signable for Manifest {
+	fn issuer(&self) -> &str {
+		&self.issuer
+	}
+
+	fn signature(&self) -> &str {
+		&self.proof.signature
+	}
+
+	fn set_signature(&mut self, signature: String) {
+		self.proof.signature = signature;
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::{
+		ManifestProof, OneOrMore, TrustScore, TrustScoreCredentialProof,
+		TrustScoreCredentialSubject,
+	};
+
+	use super::*;
+
+	fn issuer() -> (SecretKey, String) {
+		let secret_key = SecretKey::from_slice(&[7; 32]).unwrap();
+		let did = did_from_ecdsa_key(&secret_key.public_key(&Secp256k1::signing_only()));
+		(secret_key, did)
+	}
+
+	#[test]
+	fn should_sign_and_verify_credential() {
+		let (secret_key, did) = issuer();
+		let mut credential = TrustScoreCredential {
+			context: vec!["https://www.w3.org/2018/credentials/v1".to_string()],
+			id: "urn:test".to_string(),
+			type_: OneOrMore::More(vec![
+				"VerifiableCredential".to_string(),
+				"TrustScoreCredential".to_string(),
+			]),
+			issuer: did,
+			issuance_date: "2023-11-01T00:00:00Z".to_string(),
+			credential_subject: TrustScoreCredentialSubject {
+				id: "did:pkh:eth:0x90f8bf6a479f320ead074411a4b0e7944ea8c9c1".to_string(),
+				trust_score_type: "EigenTrust".to_string(),
+				trust_score: TrustScore {
+					value: 0.5,
+					value_before_discount: Some(0.75),
+					confidence: None,
+					result: None,
+					accuracy: None,
+					rank: Some(1),
+					scope: "SoftwareSecurity".to_string(),
+				},
+			},
+			proof: TrustScoreCredentialProof::default(),
+		};
+
+		credential.sign(&secret_key).unwrap();
+		assert_eq!(credential.proof.signature.len(), 2 + 65 * 2);
+		credential.verify().unwrap();
+
+		credential.credential_subject.trust_score.value = 0.6;
+		assert!(credential.verify().is_err());
+	}
+
+	#[test]
+	fn should_reject_foreign_issuer() {
+		let (secret_key, _) = issuer();
+		let mut manifest = Manifest {
+			issuer: "did:pkh:eth:0x90f8bf6a479f320ead074411a4b0e7944ea8c9c1".to_string(),
+			issuance_date: "2023-11-01T00:00:00Z".to_string(),
+			effective_date: "2023-11-01T00:00:00Z".to_string(),
+			epoch: "1698796800".to_string(),
+			scope: "SoftwareSecurity".to_string(),
+			locations: vec!["peer_scores.jsonl".to_string()],
+			trust_threshold: 0.1,
+			proof: ManifestProof::default(),
+		};
+		assert!(manifest.sign(&secret_key).is_err());
+	}
+}
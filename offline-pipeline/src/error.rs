@@ -0,0 +1,37 @@
+use attestation_transformer::error::AttTrError;
+use core_compute::error::CoreError;
+use csv::Error as CsvError;
+use linear_combiner::error::LcError;
+use rocksdb::Error as RocksDbError;
+use serde_json::Error as SerdeError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum OfflineError {
+	#[error("TransformerError: {0}")]
+	TransformerError(AttTrError),
+
+	#[error("CombinerError: {0}")]
+	CombinerError(LcError),
+
+	#[error("ComputeError: {0}")]
+	ComputeError(CoreError),
+
+	#[error("DbError: {0}")]
+	DbError(RocksDbError),
+
+	#[error("CsvError: {0}")]
+	CsvError(CsvError),
+
+	#[error("SerdeError: {0}")]
+	SerdeError(SerdeError),
+
+	#[error("IoError: {0}")]
+	IoError(std::io::Error),
+
+	#[error("NotFoundError: {0}")]
+	NotFoundError(String),
+
+	#[error("ParseError")]
+	ParseError,
+}
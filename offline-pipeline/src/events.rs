@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::path::Path;
+
+use csv::ReaderBuilder;
+
+use proto_buf::indexer::IndexerEvent;
+
+use crate::error::OfflineError;
+
+const CSV_COLUMN_INDEX_DATA: usize = 3;
+const CSV_COLUMN_SCHEMA_ID: usize = 2;
+const CSV_COLUMN_INDEX_TIMESTAMP: usize = 1;
+const CSV_COLUMN_INDEX: usize = 0;
+
+/// Read an indexer CSV export, i.e. `id;timestamp;schema_id;schema_value` records
+/// following a header, as read by the `CSVPOCTask`.
+pub fn read_csv(path: &Path) -> Result<Vec<IndexerEvent>, OfflineError> {
+	let file = File::open(path).map_err(OfflineError::IoError)?;
+	let mut csv_reader = ReaderBuilder::new().delimiter(b';').from_reader(file);
+
+	csv_reader
+		.records()
+		.map(|record| {
+			let r = record.map_err(OfflineError::CsvError)?;
+			let column = |i: usize| r.get(i).ok_or(OfflineError::ParseError);
+			let parse_err = |_| OfflineError::ParseError;
+			Ok(IndexerEvent {
+				id: column(CSV_COLUMN_INDEX)?.parse().map_err(parse_err)?,
+				timestamp: column(CSV_COLUMN_INDEX_TIMESTAMP)?.parse().map_err(parse_err)?,
+				schema_id: column(CSV_COLUMN_SCHEMA_ID)?.parse().map_err(parse_err)?,
+				schema_value: column(CSV_COLUMN_INDEX_DATA)?.to_string(),
+				..IndexerEvent::default()
+			})
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use attestation_transformer::fixtures::{self, to_csv};
+
+	use super::*;
+
+	#[test]
+	fn should_read_indexer_csv() {
+		let events = fixtures::functional();
+		let path = std::env::temp_dir().join("offline-pipeline-events-test.csv");
+		std::fs::write(&path, to_csv(&events)).unwrap();
+
+		assert_eq!(read_csv(&path).unwrap(), events);
+
+		std::fs::write(&path, "id;timestamp;schema_id;schema_value\n1;x;2;{}\n").unwrap();
+		assert!(read_csv(&path).is_err());
+		std::fs::remove_file(path).unwrap();
+	}
+}
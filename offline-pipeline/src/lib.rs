@@ -0,0 +1,258 @@
+//! The attestation transformer, the linear combiner and the core compute, run in-process
+//! over indexer events, without any of the gRPC services in between.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rocksdb::{Options, DB};
+
+use attestation_transformer::managers::attestation::AttestationManager;
+use core_compute::domain::DomainConfig;
+use core_compute::pipeline::{Pipeline, PipelineResult};
+use core_compute::pretrust::PreTrustSet;
+use linear_combiner::aggregation::Aggregation;
+use linear_combiner::decay::DecayPolicy;
+use linear_combiner::managers::checkpoint::CheckpointManager;
+use linear_combiner::managers::mapping::MappingManager;
+use linear_combiner::managers::term::TermManager;
+use proto_buf::combiner::{LtObject, Mapping};
+use proto_buf::indexer::IndexerEvent;
+use proto_buf::transformer::{Form, TermObject};
+
+use crate::error::OfflineError;
+
+pub mod error;
+pub mod events;
+pub mod verify;
+
+/// Local trust of the domains, combined from the ingested events.
+pub struct OfflinePipeline {
+	db: DB,
+	domains: Vec<DomainConfig>,
+	pipelines: BTreeMap<u32, Pipeline>,
+	aggregation: Aggregation,
+	decay: DecayPolicy,
+}
+
+impl OfflinePipeline {
+	/// Open the linear combiner storage at the given path.
+	/// It should be a fresh one, the pipelines only see the items combined from here on.
+	pub fn new(db_url: &Path, domains: Vec<DomainConfig>) -> Result<Self, OfflineError> {
+		let mut opts = Options::default();
+		opts.create_missing_column_families(true);
+		opts.create_if_missing(true);
+		let db = DB::open_cf(
+			&opts,
+			db_url,
+			vec!["attestation", "checkpoint", "index", "item", "mapping", "update"],
+		)
+		.map_err(OfflineError::DbError)?;
+		CheckpointManager::init(&db).map_err(OfflineError::CombinerError)?;
+
+		let pipelines = domains.iter().map(|x| (x.domain, Pipeline::default())).collect();
+		Ok(Self {
+			db,
+			domains,
+			pipelines,
+			aggregation: Aggregation::Latest,
+			decay: DecayPolicy::None,
+		})
+	}
+
+	/// Combine the attestations as the linear combiner does, before any event is ingested.
+	pub fn set_aggregation(&mut self, aggregation: Aggregation) {
+		self.aggregation = aggregation;
+	}
+
+	/// Decay the opinions as the linear combiner does, before any event is ingested.
+	pub fn set_decay(&mut self, decay: DecayPolicy) {
+		self.decay = decay;
+	}
+
+	pub fn domains(&self) -> &[DomainConfig] {
+		&self.domains
+	}
+
+	/// Transform and combine the events, in order, into the local trust of the domains.
+	/// Updates and revocations retract the terms of the attestations they refer to.
+	pub fn ingest(&mut self, events: Vec<IndexerEvent>) -> Result<(), OfflineError> {
+		let mut terms = Vec::new();
+		for event in events {
+			let parsed_terms = AttestationManager::parse_event(&self.db, event)
+				.map_err(OfflineError::TransformerError)?;
+			terms.extend(parsed_terms.into_iter().map(TermObject::from));
+		}
+		let items = TermManager::write_terms(&self.db, terms, &self.aggregation, &self.decay)
+			.map_err(OfflineError::CombinerError)?;
+
+		let size =
+			CheckpointManager::read_checkpoint(&self.db).map_err(OfflineError::CombinerError)?;
+		let mapping = MappingManager::read_mappings(&self.db, 0, size)
+			.map_err(OfflineError::CombinerError)?
+			.into_iter()
+			.map(|x| {
+				let Mapping { id, did } = x.into();
+				let bytes = hex::decode(did).map_err(|_| OfflineError::ParseError)?;
+				let did = String::from_utf8(bytes).map_err(|_| OfflineError::ParseError)?;
+				Ok((id, did))
+			})
+			.collect::<Result<BTreeMap<u32, String>, OfflineError>>()?;
+
+		for pipeline in self.pipelines.values_mut() {
+			pipeline.set_mapping(mapping.clone());
+		}
+		for (domain, form, item) in items {
+			// Domains that aren't computed are skipped
+			let Some(pipeline) = self.pipelines.get_mut(&domain) else {
+				continue;
+			};
+			let LtObject { x, y, value, .. } = item.into();
+			let entry = vec![(x, y, f64::from(value))];
+			if form == i32::from(Form::Trust) {
+				pipeline.update_trust(entry);
+			} else {
+				pipeline.update_distrust(entry);
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Compute the scores of a domain over everything ingested so far.
+	pub fn compute(
+		&mut self, domain: u32, pre_trust: Option<&PreTrustSet>,
+	) -> Result<PipelineResult, OfflineError> {
+		let not_found = || OfflineError::NotFoundError(format!("domain {}", domain));
+		let config = self.domains.iter().find(|x| x.domain == domain).ok_or_else(not_found)?;
+		let pipeline = self.pipelines.get_mut(&domain).ok_or_else(not_found)?;
+		pipeline.compute(pre_trust, &config.params).map_err(OfflineError::ComputeError)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use attestation_transformer::fixtures::{self, P, Q, S1, S2, X, Y, Z};
+	use core_compute::domain::SOFTWARE_SECURITY;
+	use proto_buf::indexer::EventKind;
+	use snap_score_computer::badge::{SnapBadge, UserBadge};
+
+	use super::*;
+
+	fn new_pipeline(name: &str) -> OfflinePipeline {
+		let db_url = std::env::temp_dir().join(name);
+		let _ = std::fs::remove_dir_all(&db_url);
+		OfflinePipeline::new(&db_url, DomainConfig::defaults()).unwrap()
+	}
+
+	fn pre_trust(dids: &[&str]) -> PreTrustSet {
+		dids.iter().map(|x| (x.to_string(), 1. / dids.len() as f64)).collect()
+	}
+
+	fn badge(res: &PipelineResult, snap: &str) -> SnapBadge {
+		res.snaps[snap].1
+	}
+
+	fn adjusted(res: &PipelineResult, did: &str) -> f64 {
+		res.peers.iter().find(|x| x.did == did).unwrap().adjusted
+	}
+
+	#[test]
+	fn should_resist_sybil_clique() {
+		let mut pipeline = new_pipeline("op-sybil-attack-test-storage");
+		pipeline.ingest(fixtures::sybil_attack()).unwrap();
+		let res = pipeline.compute(SOFTWARE_SECURITY, Some(&pre_trust(&[P, Q]))).unwrap();
+
+		// The clique's endorsements don't outweigh the disputes of the pre-trusted peers
+		assert_eq!(badge(&res, S1), SnapBadge::Reported);
+		assert_eq!(badge(&res, S2), SnapBadge::Endorsed);
+		// Only y, trusted by q, keeps a positive standing
+		assert!(adjusted(&res, X) < 0.);
+		assert!(adjusted(&res, Z) < 0.);
+		assert!(adjusted(&res, Y) < adjusted(&res, Q));
+	}
+
+	#[test]
+	fn should_keep_sleeping_agent_from_reporting_snap() {
+		let mut pipeline = new_pipeline("op-sleeping-agent-test-storage");
+		let pre_trust = pre_trust(&[P, Q]);
+		let (first, second) = fixtures::sleeping_agent_attack();
+
+		pipeline.ingest(first).unwrap();
+		let res = pipeline.compute(SOFTWARE_SECURITY, Some(&pre_trust)).unwrap();
+		assert_eq!(badge(&res, S2), SnapBadge::Endorsed);
+		assert_eq!(res.users[Z], UserBadge::HighlyTrusted);
+		assert_eq!(res.users[X], UserBadge::Reported);
+
+		// z turns on s2, which p and q still endorse.
+		// The dispute replaces the endorsement of z, a sole dissenting auditor holds s2 in review
+		pipeline.ingest(second).unwrap();
+		let res = pipeline.compute(SOFTWARE_SECURITY, Some(&pre_trust)).unwrap();
+		assert_eq!(badge(&res, S2), SnapBadge::InReview);
+		assert!(res.snaps.contains_key(S1));
+	}
+
+	#[test]
+	fn should_age_opinions_across_ingests() {
+		let pre_trust = pre_trust(&[P, Q]);
+		let (first, second) = fixtures::sleeping_agent_attack();
+		let decay = DecayPolicy::Window(5000);
+
+		// The opinions of the first ingest age as the later ones come in
+		let mut pipeline = new_pipeline("op-decay-incremental-test-storage");
+		pipeline.set_decay(decay);
+		pipeline.ingest(first.clone()).unwrap();
+		pipeline.compute(SOFTWARE_SECURITY, Some(&pre_trust)).unwrap();
+		pipeline.ingest(second.clone()).unwrap();
+		let res = pipeline.compute(SOFTWARE_SECURITY, Some(&pre_trust)).unwrap();
+
+		let mut full = new_pipeline("op-decay-full-test-storage");
+		full.set_decay(decay);
+		full.ingest([first, second].concat()).unwrap();
+		let expected = full.compute(SOFTWARE_SECURITY, Some(&pre_trust)).unwrap();
+
+		for (a, b) in res.peers.iter().zip(&expected.peers) {
+			assert_eq!(a.did, b.did);
+			assert!((a.adjusted - b.adjusted).abs() < 1e-6);
+		}
+		assert_eq!(res.users, expected.users);
+		assert_eq!(badge(&res, S2), badge(&expected, S2));
+	}
+
+	#[test]
+	fn should_restore_endorsement_once_dispute_is_revoked() {
+		let mut pipeline = new_pipeline("op-revocation-test-storage");
+		let pre_trust = pre_trust(&[P, Q]);
+		let (first, mut second) = fixtures::sleeping_agent_attack();
+		let dispute = second.last_mut().unwrap();
+		dispute.attestation_id = "0x02".to_string();
+		let timestamp = dispute.timestamp + 1000;
+
+		pipeline.ingest(first).unwrap();
+		pipeline.ingest(second).unwrap();
+		let res = pipeline.compute(SOFTWARE_SECURITY, Some(&pre_trust)).unwrap();
+		assert_eq!(badge(&res, S2), SnapBadge::InReview);
+
+		let revocation = IndexerEvent {
+			timestamp,
+			kind: EventKind::Revoked.into(),
+			attestation_id: "0x02".to_string(),
+			..IndexerEvent::default()
+		};
+		pipeline.ingest(vec![revocation]).unwrap();
+		let res = pipeline.compute(SOFTWARE_SECURITY, Some(&pre_trust)).unwrap();
+		assert_eq!(badge(&res, S2), SnapBadge::Endorsed);
+	}
+
+	#[test]
+	fn should_not_badge_snaps_reviewed_by_sybils_only() {
+		let mut pipeline = new_pipeline("op-100-sybils-test-storage");
+		pipeline.ingest(fixtures::sybils_100()).unwrap();
+		let res = pipeline.compute(SOFTWARE_SECURITY, None).unwrap();
+
+		// None of the sybils is endorsed by a pre-trusted peer
+		assert_eq!(res.trust_threshold, None);
+		assert!(res.users.is_empty());
+		assert_eq!(badge(&res, S1), SnapBadge::InsufficientReviews);
+		assert_eq!(badge(&res, S2), SnapBadge::InsufficientReviews);
+	}
+}
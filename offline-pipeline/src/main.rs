@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+
+use clap::Parser as ClapParser;
+
+use core_compute::domain::DomainConfig;
+use core_compute::pretrust::{load_file, normalize};
+use linear_combiner::aggregation::Aggregation;
+use linear_combiner::decay::DecayPolicy;
+use offline_pipeline::events::read_csv;
+use offline_pipeline::verify::{computed_scores, diff, read_published};
+use offline_pipeline::OfflinePipeline;
+
+/// Recompute the scores from an indexer CSV export, entirely in-process,
+/// and compare them against published score credentials.
+#[derive(ClapParser)]
+struct Args {
+	/// Indexer CSV export, with `id;timestamp;schema_id;schema_value` records.
+	#[arg(long, value_name = "FILE")]
+	csv: PathBuf,
+
+	/// Published credentials, e.g. `peer_scores.jsonl` or `snap_scores.jsonl`.
+	#[arg(long, value_name = "FILE")]
+	scores: PathBuf,
+
+	/// Directory of the `<domain>.csv` pre-trust files, as used by core-computer.
+	#[arg(long, value_name = "DIR", default_value = "./pre-trust")]
+	pre_trust_dir: PathBuf,
+
+	/// Largest accepted difference between a published and a recomputed score.
+	#[arg(long, default_value_t = 1e-4)]
+	tolerance: f64,
+}
+
+fn verify(args: &Args, db_url: &Path) -> Result<bool, Box<dyn Error>> {
+	let events = read_csv(&args.csv)?;
+	let published = read_published(&args.scores)?;
+
+	// Compute parameters are overridden the same way as for core-computer
+	let mut pipeline = OfflinePipeline::new(db_url, DomainConfig::from_env()?)?;
+	let decay = DecayPolicy::from_env()?;
+	pipeline.set_aggregation(Aggregation::from_env(&decay)?);
+	pipeline.set_decay(decay);
+	pipeline.ingest(events)?;
+
+	let domains = pipeline.domains().to_vec();
+	let mut computed = BTreeMap::new();
+	for config in domains {
+		let path = args.pre_trust_dir.join(format!("{}.csv", config.domain));
+		let pre_trust = if path.exists() { Some(normalize(load_file(&path)?)) } else { None };
+		let res = pipeline.compute(config.domain, pre_trust.as_ref())?;
+		computed.extend(computed_scores(&config.scope, &res));
+	}
+
+	let mismatches = diff(&published, &computed, args.tolerance);
+	for mismatch in &mismatches {
+		println!("{}", mismatch);
+	}
+	println!(
+		"Verified {} published scores: {} mismatches",
+		published.len(),
+		mismatches.len()
+	);
+	Ok(mismatches.is_empty())
+}
+
+fn main() {
+	let args = Args::parse();
+
+	// The combiner storage only lives for the run
+	let db_url = env::temp_dir().join(format!("offline-pipeline-{}", process::id()));
+	let res = verify(&args, &db_url);
+	let _ = fs::remove_dir_all(&db_url);
+
+	match res {
+		Ok(true) => {},
+		Ok(false) => process::exit(1),
+		Err(e) => {
+			eprintln!("Failed to verify: {}", e);
+			process::exit(2);
+		},
+	}
+}
@@ -0,0 +1,188 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use core_compute::pipeline::PipelineResult;
+use core_compute::publisher::{peer_scores, snap_scores, PEER_SCORE_TYPE, SNAP_SCORE_TYPE};
+use mm_spd_vc::proof::Signable;
+use mm_spd_vc::{TrustScore, TrustScoreCredential};
+
+use crate::error::OfflineError;
+
+/// Scope, score type and subject of a score.
+pub type ScoreKey = (String, String, String);
+
+/// Difference between a published score and the recomputed one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Mismatch {
+	/// The credential isn't signed by its issuer.
+	InvalidSignature(ScoreKey),
+	/// Published, but not recomputed.
+	Missing(ScoreKey),
+	/// Recomputed, but not published along with the other scores of its scope and type.
+	Unpublished(ScoreKey),
+	Score {
+		key: ScoreKey,
+		published: Box<TrustScore>,
+		computed: Box<TrustScore>,
+	},
+}
+
+impl fmt::Display for Mismatch {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::InvalidSignature((scope, score_type, subject)) => {
+				write!(f, "{} {} {}: invalid signature", scope, score_type, subject)
+			},
+			Self::Missing((scope, score_type, subject)) => {
+				write!(f, "{} {} {}: not recomputed", scope, score_type, subject)
+			},
+			Self::Unpublished((scope, score_type, subject)) => {
+				write!(f, "{} {} {}: not published", scope, score_type, subject)
+			},
+			Self::Score { key: (scope, score_type, subject), published, computed } => write!(
+				f,
+				"{} {} {}: published {:?}, recomputed {:?}",
+				scope, score_type, subject, published, computed
+			),
+		}
+	}
+}
+
+/// Read the published credentials, one JSON per line.
+pub fn read_published(path: &Path) -> Result<Vec<TrustScoreCredential>, OfflineError> {
+	let content = fs::read_to_string(path).map_err(OfflineError::IoError)?;
+	content
+		.lines()
+		.filter(|line| !line.trim().is_empty())
+		.map(|line| serde_json::from_str(line).map_err(OfflineError::SerdeError))
+		.collect()
+}
+
+/// Scores of a domain, as they would be published under the given scope.
+pub fn computed_scores(scope: &str, res: &PipelineResult) -> BTreeMap<ScoreKey, TrustScore> {
+	let peers = peer_scores(scope, &res.peers)
+		.into_iter()
+		.map(|(did, score)| ((scope.to_string(), PEER_SCORE_TYPE.to_string(), did), score));
+	let snaps = snap_scores(scope, &res.snaps).into_iter().map(|(snap, score)| {
+		(
+			(scope.to_string(), SNAP_SCORE_TYPE.to_string(), snap),
+			score,
+		)
+	});
+	peers.chain(snaps).collect()
+}
+
+fn is_close(a: Option<f64>, b: Option<f64>, tolerance: f64) -> bool {
+	match (a, b) {
+		(Some(a), Some(b)) => (a - b).abs() <= tolerance,
+		(a, b) => a == b,
+	}
+}
+
+/// Whether the scores agree, up to the tolerance. Ranks aren't compared,
+/// as peers with (almost) equal scores may be ranked either way.
+fn is_matching(published: &TrustScore, computed: &TrustScore, tolerance: f64) -> bool {
+	is_close(Some(published.value), Some(computed.value), tolerance)
+		&& is_close(
+			published.value_before_discount, computed.value_before_discount, tolerance,
+		) && is_close(published.confidence, computed.confidence, tolerance)
+		&& published.result == computed.result
+}
+
+/// Compare the published credentials against the recomputed scores.
+/// Only the scopes and score types found among the published credentials are compared.
+pub fn diff(
+	published: &[TrustScoreCredential], computed: &BTreeMap<ScoreKey, TrustScore>, tolerance: f64,
+) -> Vec<Mismatch> {
+	let mut mismatches = Vec::new();
+	let mut published_keys = BTreeSet::new();
+	for credential in published {
+		let subject = &credential.credential_subject;
+		let key = (
+			subject.trust_score.scope.clone(),
+			subject.trust_score_type.clone(),
+			subject.id.clone(),
+		);
+		published_keys.insert(key.clone());
+
+		if credential.verify().is_err() {
+			mismatches.push(Mismatch::InvalidSignature(key));
+			continue;
+		}
+		match computed.get(&key) {
+			None => mismatches.push(Mismatch::Missing(key)),
+			Some(score) if !is_matching(&subject.trust_score, score, tolerance) => {
+				let published = Box::new(subject.trust_score.clone());
+				let computed = Box::new(score.clone());
+				mismatches.push(Mismatch::Score { key, published, computed });
+			},
+			Some(_) => {},
+		}
+	}
+
+	let published_types: BTreeSet<(&String, &String)> =
+		published_keys.iter().map(|(scope, score_type, _)| (scope, score_type)).collect();
+	for key in computed.keys() {
+		let (scope, score_type, _) = key;
+		if published_types.contains(&(scope, score_type)) && !published_keys.contains(key) {
+			mismatches.push(Mismatch::Unpublished(key.clone()));
+		}
+	}
+	mismatches
+}
+
+#[cfg(test)]
+mod test {
+	use core_compute::publisher::{PeerResult, Publisher};
+	use secp256k1::SecretKey;
+
+	use super::*;
+
+	fn peer(did: &str, positive: f64, adjusted: f64) -> PeerResult {
+		PeerResult { did: did.to_string(), positive, adjusted }
+	}
+
+	#[test]
+	fn should_diff_published_scores() {
+		let scope = "SoftwareSecurity";
+		let publisher = Publisher::new(SecretKey::from_slice(&[7; 32]).unwrap(), "".into());
+		let peers = vec![peer("alice", 0.25, 0.125), peer("bob", 0.75, 0.75)];
+		let published = publisher.peer_credentials(1700000000, scope, &peers).unwrap();
+
+		let res = PipelineResult { peers: peers.clone(), ..PipelineResult::default() };
+		assert!(diff(&published, &computed_scores(scope, &res), 1e-6).is_empty());
+
+		// Within the tolerance
+		let res = PipelineResult {
+			peers: vec![peer("alice", 0.25, 0.1250001), peer("bob", 0.75, 0.75)],
+			..PipelineResult::default()
+		};
+		assert!(diff(&published, &computed_scores(scope, &res), 1e-6).is_empty());
+
+		let res = PipelineResult {
+			peers: vec![peer("alice", 0.25, 0.25), peer("carol", 0.75, 0.75)],
+			..PipelineResult::default()
+		};
+		let mismatches = diff(&published, &computed_scores(scope, &res), 1e-6);
+		let key = |did: &str| {
+			(
+				scope.to_string(),
+				PEER_SCORE_TYPE.to_string(),
+				did.to_string(),
+			)
+		};
+		assert_eq!(mismatches.len(), 3);
+		// Published ranked by score
+		assert_eq!(mismatches[0], Mismatch::Missing(key("bob")));
+		assert!(matches!(&mismatches[1], Mismatch::Score { key: k, .. } if *k == key("alice")));
+		assert_eq!(mismatches[2], Mismatch::Unpublished(key("carol")));
+
+		let mut tampered = published.clone();
+		tampered[0].credential_subject.trust_score.value = 1.;
+		let res = PipelineResult { peers, ..PipelineResult::default() };
+		let mismatches = diff(&tampered, &computed_scores(scope, &res), 1e-6);
+		assert_eq!(mismatches, vec![Mismatch::InvalidSignature(key("bob"))]);
+	}
+}
@@ -13,3 +13,7 @@ pub mod transformer {
 pub mod combiner {
 	tonic::include_proto!("combiner");
 }
+
+pub mod eigentrust {
+	tonic::include_proto!("eigentrust");
+}
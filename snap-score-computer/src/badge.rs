@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::score::{is_snap, Edge, SnapScore};
+
+/// Community sentiment badge of a snap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapBadge {
+	InsufficientReviews,
+	Endorsed,
+	InReview,
+	Reported,
+}
+
+/// Community sentiment badge of a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserBadge {
+	HighlyTrusted,
+	Reported,
+}
+
+/// Highly trusted auditors are the peers directly endorsed by the pre-trusted peers.
+pub fn highly_trusted_auditors(
+	trust: &[Edge], pre_trust: &HashMap<String, f64>,
+) -> BTreeSet<String> {
+	trust
+		.iter()
+		.filter(|(truster, trustee, value)| {
+			let is_pre_trusted = pre_trust.get(truster).map_or(false, |x| *x > 0.);
+			is_pre_trusted && *value > 0. && truster != trustee && !is_snap(trustee)
+		})
+		.map(|(_, trustee, _)| trustee.clone())
+		.collect()
+}
+
+/// `T+(d)` of the weakest highly trusted auditor `d`,
+/// i.e. the lowest positive-LT-only score among the auditors.
+/// None if there are no auditors with a positive standing.
+pub fn weakest_auditor_score(
+	auditors: &BTreeSet<String>, positive_scores: &HashMap<String, f64>,
+) -> Option<f64> {
+	auditors
+		.iter()
+		.filter_map(|x| positive_scores.get(x).copied())
+		.filter(|x| *x > 0.)
+		.min_by(|a, b| a.total_cmp(b))
+}
+
+/// `(R_E, R_R)` thresholds of a snap with the given confidence,
+/// such that a sole dissenting weakest auditor keeps it In Review.
+pub fn thresholds(confidence: f64, weakest: f64) -> (f64, f64) {
+	let ratio = weakest / confidence;
+	(1. - ratio, ratio)
+}
+
+/// Classify a snap by its score, against the weakest auditor's `T+(d)`.
+/// Without any highly trusted auditor no snap can gather sufficient reviews.
+pub fn snap_badge(score: &SnapScore, weakest: Option<f64>) -> SnapBadge {
+	let weakest = match weakest {
+		Some(weakest) if score.confidence >= weakest => weakest,
+		_ => return SnapBadge::InsufficientReviews,
+	};
+
+	// Below `2 T+(d)` of confidence the thresholds cross,
+	// and a value past both of them is contested
+	let (endorsed, reported) = thresholds(score.confidence, weakest);
+	if score.value > endorsed && score.value < reported {
+		SnapBadge::InReview
+	} else if score.value > endorsed {
+		SnapBadge::Endorsed
+	} else if score.value < reported {
+		SnapBadge::Reported
+	} else {
+		SnapBadge::InReview
+	}
+}
+
+pub fn snap_badges(
+	scores: &BTreeMap<String, SnapScore>, weakest: Option<f64>,
+) -> BTreeMap<String, SnapBadge> {
+	scores.iter().map(|(snap, score)| (snap.clone(), snap_badge(score, weakest))).collect()
+}
+
+/// Highly trusted auditors earn the Highly Trusted badge,
+/// while users distrusted by any of them are Reported, which takes precedence.
+pub fn user_badges(auditors: &BTreeSet<String>, distrust: &[Edge]) -> BTreeMap<String, UserBadge> {
+	let mut badges: BTreeMap<String, UserBadge> =
+		auditors.iter().map(|x| (x.clone(), UserBadge::HighlyTrusted)).collect();
+
+	let reported = distrust.iter().filter(|(truster, trustee, value)| {
+		auditors.contains(truster) && *value > 0. && truster != trustee && !is_snap(trustee)
+	});
+	for (_, trustee, _) in reported {
+		badges.insert(trustee.clone(), UserBadge::Reported);
+	}
+	badges
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn edge(truster: &str, trustee: &str) -> Edge {
+		(truster.to_string(), trustee.to_string(), 1.)
+	}
+
+	#[test]
+	fn should_find_highly_trusted_auditors() {
+		let pre_trust = HashMap::from([("alice".to_string(), 1.)]);
+		let trust = vec![
+			edge("alice", "bob"),
+			edge("alice", "carol"),
+			edge("alice", "snap://a"),
+			edge("bob", "dave"),
+		];
+
+		let auditors = highly_trusted_auditors(&trust, &pre_trust);
+		assert_eq!(
+			auditors,
+			BTreeSet::from(["bob".to_string(), "carol".to_string()])
+		);
+
+		let positive_scores =
+			HashMap::from([("bob".to_string(), 0.25), ("carol".to_string(), 0.125)]);
+		assert_eq!(
+			weakest_auditor_score(&auditors, &positive_scores),
+			Some(0.125)
+		);
+		assert_eq!(
+			weakest_auditor_score(&BTreeSet::new(), &positive_scores),
+			None
+		);
+
+		let distrust = vec![edge("bob", "carol"), edge("dave", "bob"), edge("carol", "erin")];
+		let badges = user_badges(&auditors, &distrust);
+		assert_eq!(badges["bob"], UserBadge::HighlyTrusted);
+		assert_eq!(badges["carol"], UserBadge::Reported);
+		assert_eq!(badges["erin"], UserBadge::Reported);
+		assert!(!badges.contains_key("dave"));
+	}
+
+	#[test]
+	fn should_classify_snaps() {
+		let weakest = Some(0.25);
+		let score = |value, confidence| SnapScore { value, confidence };
+
+		assert_eq!(
+			snap_badge(&score(1., 0.125), weakest),
+			SnapBadge::InsufficientReviews
+		);
+		assert_eq!(
+			snap_badge(&score(1., 1.), None),
+			SnapBadge::InsufficientReviews
+		);
+		// R_E = 0.75, R_R = 0.25
+		assert_eq!(snap_badge(&score(0.875, 1.), weakest), SnapBadge::Endorsed);
+		assert_eq!(snap_badge(&score(0.75, 1.), weakest), SnapBadge::InReview);
+		assert_eq!(snap_badge(&score(0.25, 1.), weakest), SnapBadge::InReview);
+		assert_eq!(snap_badge(&score(0.125, 1.), weakest), SnapBadge::Reported);
+		// A sole auditor at the threshold is unanimous
+		assert_eq!(snap_badge(&score(0., 0.25), weakest), SnapBadge::Reported);
+	}
+
+	#[test]
+	fn should_keep_snaps_between_crossed_thresholds_in_review() {
+		let weakest = Some(0.25);
+		let score = |value| SnapScore { value, confidence: 0.375 };
+
+		// R_E = 1/3, R_R = 2/3
+		assert_eq!(snap_badge(&score(0.5), weakest), SnapBadge::InReview);
+		assert_eq!(snap_badge(&score(0.875), weakest), SnapBadge::Endorsed);
+		assert_eq!(snap_badge(&score(0.125), weakest), SnapBadge::Reported);
+	}
+}
@@ -0,0 +1,108 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::score::{opinions, Edge};
+
+/// Contribution of a peer to the score of a subject.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution {
+	pub peer: String,
+	/// `T(p)`: trust standing of the peer.
+	pub trust: f64,
+	/// The peer's opinion about the subject, e.g. `R(s,p)` for a snap.
+	pub opinion: f64,
+	/// Share of the peer in the score, e.g. `T(p) / C(s)` for a snap.
+	pub share: f64,
+}
+
+/// Why a subject got its score and badge.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Explanation {
+	/// Top contributors, by share.
+	pub contributions: Vec<Contribution>,
+	/// Highly trusted auditors behind the badge.
+	pub auditors: Vec<String>,
+}
+
+/// Keep the `n` largest contributions, largest first.
+pub fn top_contributions(mut contributions: Vec<Contribution>, n: usize) -> Vec<Contribution> {
+	contributions.sort_by(|a, b| b.share.total_cmp(&a.share).then_with(|| a.peer.cmp(&b.peer)));
+	contributions.truncate(n);
+	contributions
+}
+
+/// Explain the snap scores: the top `n` opiners by their share of `C(s)`,
+/// and the highly trusted auditors among all the opiners, whose (unanimous) opinion
+/// decides the badge. Only opiners with a positive standing count, as for the score.
+pub fn explain_snaps(
+	peer_scores: &HashMap<String, f64>, trust: &[Edge], distrust: &[Edge],
+	auditors: &BTreeSet<String>, n: usize,
+) -> BTreeMap<String, Explanation> {
+	let mut contributions: BTreeMap<&str, Vec<Contribution>> = BTreeMap::new();
+	for ((snap, peer), (trust, distrust)) in opinions(trust, distrust) {
+		let snap_contributions = contributions.entry(snap).or_default();
+		let standing = peer_scores.get(peer).copied().unwrap_or(0.);
+		let total = trust + distrust;
+		if standing <= 0. || total <= 0. {
+			continue;
+		}
+		snap_contributions.push(Contribution {
+			peer: peer.to_string(),
+			trust: standing,
+			opinion: trust / total,
+			share: standing,
+		});
+	}
+
+	contributions
+		.into_iter()
+		.map(|(snap, mut contributions)| {
+			let confidence: f64 = contributions.iter().map(|x| x.share).sum();
+			contributions.iter_mut().for_each(|x| x.share /= confidence);
+
+			let contributions = top_contributions(contributions, usize::MAX);
+			let snap_auditors = contributions
+				.iter()
+				.filter(|x| auditors.contains(&x.peer))
+				.map(|x| x.peer.clone())
+				.collect();
+			let contributions = top_contributions(contributions, n);
+			(
+				snap.to_string(),
+				Explanation { contributions, auditors: snap_auditors },
+			)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn edge(peer: &str, snap: &str) -> Edge {
+		(peer.to_string(), snap.to_string(), 50.)
+	}
+
+	#[test]
+	fn should_explain_snap_scores() {
+		let peer_scores: HashMap<String, f64> =
+			[("alice", 0.5), ("bob", 0.25), ("carol", 0.25), ("dave", -0.25)]
+				.into_iter()
+				.map(|(did, score)| (did.to_string(), score))
+				.collect();
+		let trust = vec![edge("alice", "snap://a"), edge("dave", "snap://a")];
+		let distrust = vec![edge("bob", "snap://a"), edge("carol", "snap://a")];
+		let auditors = BTreeSet::from(["bob".to_string(), "dave".to_string()]);
+
+		let explanations = explain_snaps(&peer_scores, &trust, &distrust, &auditors, 2);
+		let a = &explanations["snap://a"];
+		assert_eq!(
+			a.contributions,
+			vec![
+				Contribution { peer: "alice".to_string(), trust: 0.5, opinion: 1., share: 0.5 },
+				Contribution { peer: "bob".to_string(), trust: 0.25, opinion: 0., share: 0.25 },
+			]
+		);
+		// dave's standing isn't positive, so the opinion doesn't count
+		assert_eq!(a.auditors, vec!["bob".to_string()]);
+	}
+}
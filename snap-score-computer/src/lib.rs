@@ -0,0 +1,3 @@
+pub mod badge;
+pub mod explain;
+pub mod score;
@@ -0,0 +1,106 @@
+use std::collections::{BTreeMap, HashMap};
+
+const SNAP_PREFIX: &str = "snap://";
+
+/// Trust (endorsement) or distrust (report) edge from a peer to a snap,
+/// as emitted by `StatusSchema::into_term`.
+pub type Edge = (String, String, f64);
+
+/// Security score of a snap.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapScore {
+	/// `R_c(s)`: opinions weighted by the opiners' trust standing, within [0, 1].
+	/// Zero if no peer with a positive standing opined on the snap.
+	pub value: f64,
+	/// `C(s)`: cumulative trust standing of the opiners.
+	pub confidence: f64,
+}
+
+/// Whether the participant is a snap (as opposed to a peer).
+pub fn is_snap(did: &str) -> bool {
+	did.starts_with(SNAP_PREFIX)
+}
+
+/// Total trust and distrust weight of each peer towards each snap, keyed by (snap, peer).
+pub(crate) fn opinions<'a>(
+	trust: &'a [Edge], distrust: &'a [Edge],
+) -> BTreeMap<(&'a str, &'a str), (f64, f64)> {
+	let mut opinions: BTreeMap<(&str, &str), (f64, f64)> = BTreeMap::new();
+	for (peer, snap, weight) in trust.iter().filter(|(_, snap, _)| is_snap(snap)) {
+		opinions.entry((snap, peer)).or_default().0 += weight;
+	}
+	for (peer, snap, weight) in distrust.iter().filter(|(_, snap, _)| is_snap(snap)) {
+		opinions.entry((snap, peer)).or_default().1 += weight;
+	}
+	opinions
+}
+
+/// Compute the security score of every snap that received an opinion:
+/// `C(s) = Σ T(p)` and `R_c(s) = Σ R(s,p)T(p) / C(s)`.
+///
+/// `peer_scores` are the distrust-adjusted SoftwareSecurity scores `T(p)`.
+/// A peer's opinion `R(s,p)` is the share of trust in its total (trust + distrust) weight
+/// towards the snap, i.e. 1 for an endorsement and 0 for a report.
+/// Only peers with a positive standing count.
+pub fn compute(
+	peer_scores: &HashMap<String, f64>, trust: &[Edge], distrust: &[Edge],
+) -> BTreeMap<String, SnapScore> {
+	// snap -> (Σ R(s,p)T(p), Σ T(p))
+	let mut sums: BTreeMap<&str, (f64, f64)> = BTreeMap::new();
+	for ((snap, peer), (trust, distrust)) in opinions(trust, distrust) {
+		let sum = sums.entry(snap).or_default();
+		let standing = peer_scores.get(peer).copied().unwrap_or(0.);
+		let total = trust + distrust;
+		if standing <= 0. || total <= 0. {
+			continue;
+		}
+		sum.0 += trust / total * standing;
+		sum.1 += standing;
+	}
+
+	sums.into_iter()
+		.map(|(snap, (weighted, confidence))| {
+			let value = if confidence > 0. { weighted / confidence } else { 0. };
+			(snap.to_string(), SnapScore { value, confidence })
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn edge(peer: &str, snap: &str) -> Edge {
+		(peer.to_string(), snap.to_string(), 50.)
+	}
+
+	#[test]
+	fn should_weight_opinions_by_trust_standing() {
+		let peer_scores: HashMap<String, f64> =
+			[("alice", 0.5), ("bob", 0.25), ("carol", -0.25), ("dave", 0.)]
+				.into_iter()
+				.map(|(did, score)| (did.to_string(), score))
+				.collect();
+		let trust =
+			vec![edge("alice", "snap://a"), edge("carol", "snap://a"), edge("bob", "snap://b")];
+		let distrust = vec![edge("bob", "snap://a"), edge("dave", "snap://c")];
+
+		let scores = compute(&peer_scores, &trust, &distrust);
+
+		let a = &scores["snap://a"];
+		assert_eq!(a.confidence, 0.75);
+		assert_eq!(a.value, 0.5 / 0.75);
+		assert_eq!(
+			scores["snap://b"],
+			SnapScore { value: 1., confidence: 0.25 }
+		);
+		assert_eq!(scores["snap://c"], SnapScore::default());
+	}
+
+	#[test]
+	fn should_ignore_peer_to_peer_edges() {
+		let peer_scores = HashMap::from([("alice".to_string(), 1.)]);
+		let scores = compute(&peer_scores, &[edge("alice", "bob")], &[]);
+		assert!(scores.is_empty());
+	}
+}